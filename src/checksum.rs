@@ -8,14 +8,19 @@
  */
 use std::fs::File;
 use std::path::Path;
-use std::io::Read;
+use std::io::{ErrorKind,Read};
 use crypto::digest::Digest;
 use crypto::sha2::Sha256;
 use crypto::md5::Md5;
 
+const READ_BUFFER_SIZE : usize = 1 << 16;
+const CRC32_POLYNOMIAL : u32 = 0xedb88320;
+
+type Crc32Tables = [[u32; 256]; 8];
+
 #[derive(Clone, Debug)]
 pub enum ChecksumFunction {
-  SHA256, MD5
+  SHA256, MD5, CRC32
 }
 
 pub fn checksum_function_from_str(s: &str) -> Result<ChecksumFunction, ::Error> {
@@ -26,10 +31,33 @@ pub fn checksum_function_from_str(s: &str) -> Result<ChecksumFunction, ::Error>
   };
 }
 
+/**
+ * Like checksum_function_from_str, but also accepts CRC-32, which is only used
+ * by snapshots that were imported from SFV files
+ */
+pub fn stored_checksum_function_from_str(s: &str) -> Result<ChecksumFunction, ::Error> {
+  return match s {
+    "crc32" => Ok(ChecksumFunction::CRC32),
+    _ => checksum_function_from_str(s),
+  };
+}
+
 pub fn checksum_function_to_str(f: &ChecksumFunction) -> String {
   return match f {
     &ChecksumFunction::SHA256 => "sha256".into(),
     &ChecksumFunction::MD5 => "md5".into(),
+    &ChecksumFunction::CRC32 => "crc32".into(),
+  };
+}
+
+/**
+ * The number of hex digits in a checksum computed by the function
+ */
+pub fn checksum_length(f: &ChecksumFunction) -> usize {
+  return match f {
+    &ChecksumFunction::SHA256 => 64,
+    &ChecksumFunction::MD5 => 32,
+    &ChecksumFunction::CRC32 => 8,
   };
 }
 
@@ -37,6 +65,7 @@ pub fn compute(checksum_fn: ChecksumFunction, data: &[u8]) -> String {
   match checksum_fn {
     ChecksumFunction::SHA256 => return compute_sha256(data),
     ChecksumFunction::MD5 => return compute_md5(data),
+    ChecksumFunction::CRC32 => return compute_crc32(data),
   };
 }
 
@@ -44,6 +73,7 @@ pub fn compute_file(checksum_fn: ChecksumFunction, path: &Path) -> Result<String
   match checksum_fn {
     ChecksumFunction::SHA256 => return compute_file_sha256(path),
     ChecksumFunction::MD5 => return compute_file_md5(path),
+    ChecksumFunction::CRC32 => return compute_file_crc32(path),
   };
 }
 
@@ -83,3 +113,94 @@ fn compute_file_md5(path: &Path) -> Result<String, ::Error> {
   return Ok(digest.result_str());
 }
 
+/**
+ * CRC-32 (IEEE 802.3) as used by SFV files. This is not a cryptographic hash
+ * and only supported for importing existing SFV files
+ */
+fn compute_crc32(data: &[u8]) -> String {
+  return format!("{:08x}", !update_crc32(&crc32_tables(), 0xffffffff, data));
+}
+
+/**
+ * Lookup tables for computing the CRC eight bytes at a time ("slicing-by-8").
+ * Table 0 is the classic bytewise table; table n advances a byte by n more
+ * bytes of zeros
+ */
+fn crc32_tables() -> Crc32Tables {
+  let mut tables = [[0u32; 256]; 8];
+
+  for i in 0..256 {
+    let mut crc = i as u32;
+    for _ in 0..8 {
+      crc = if crc & 1 == 1 { (crc >> 1) ^ CRC32_POLYNOMIAL } else { crc >> 1 };
+    }
+
+    tables[0][i] = crc;
+  }
+
+  for i in 0..256 {
+    for t in 1..8 {
+      let prev = tables[t - 1][i];
+      tables[t][i] = (prev >> 8) ^ tables[0][(prev & 0xff) as usize];
+    }
+  }
+
+  return tables;
+}
+
+fn update_crc32(tables: &Crc32Tables, crc: u32, data: &[u8]) -> u32 {
+  let mut crc = crc;
+
+  for chunk in data.chunks(8) {
+    if chunk.len() < 8 {
+      for byte in chunk {
+        crc = (crc >> 8) ^ tables[0][((crc ^ *byte as u32) & 0xff) as usize];
+      }
+
+      break;
+    }
+
+    let lo = crc ^ read_u32_le(&chunk[0..4]);
+    let hi = read_u32_le(&chunk[4..8]);
+    crc =
+        tables[7][(lo & 0xff) as usize] ^
+        tables[6][((lo >> 8) & 0xff) as usize] ^
+        tables[5][((lo >> 16) & 0xff) as usize] ^
+        tables[4][(lo >> 24) as usize] ^
+        tables[3][(hi & 0xff) as usize] ^
+        tables[2][((hi >> 8) & 0xff) as usize] ^
+        tables[1][((hi >> 16) & 0xff) as usize] ^
+        tables[0][(hi >> 24) as usize];
+  }
+
+  return crc;
+}
+
+fn read_u32_le(data: &[u8]) -> u32 {
+  return
+      data[0] as u32 |
+      (data[1] as u32) << 8 |
+      (data[2] as u32) << 16 |
+      (data[3] as u32) << 24;
+}
+
+fn compute_file_crc32(path: &Path) -> Result<String, ::Error> {
+  let mut file = match File::open(&path) {
+    Ok(f) => f,
+    Err(e) => return Err(e.to_string()),
+  };
+
+  let tables = crc32_tables();
+  let mut crc = 0xffffffffu32;
+  let mut buf = vec!(0u8; READ_BUFFER_SIZE);
+  loop {
+    match file.read(&mut buf) {
+      Ok(0) => break,
+      Ok(n) => crc = update_crc32(&tables, crc, &buf[..n]),
+      Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+      Err(e) => return Err(e.to_string()),
+    };
+  }
+
+  return Ok(format!("{:08x}", !crc));
+}
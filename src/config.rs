@@ -0,0 +1,257 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path,PathBuf};
+use getopts;
+
+const ENV_PREFIX : &'static str = "IC_";
+
+/* the options that locate the config file in the index directory */
+const LOCATION_OPTIONS : &'static [&'static str] = &["data_dir", "index_dir"];
+
+/**
+ * Options that mean different things in different commands, with the commands
+ * in which a plain key or 'IC_<OPTION>' variable sets them. All other commands
+ * only take a '<command>.<option>' key for them
+ */
+const SCOPED_OPTIONS : &'static [(&'static str, &'static [&'static str])] = &[
+  ("format", &["ack", "duplicates", "index", "log", "status", "verify"]),
+  ("from", &[]),
+  ("to", &[]),
+];
+
+/**
+ * A configuration file consisting of 'key = value' lines. Empty lines and lines
+ * starting with '#' are ignored. Keys may be repeated.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+  entries: Vec<(String, String)>,
+}
+
+impl Config {
+
+  pub fn load(path: &Path) -> Result<Config, ::Error> {
+    let mut config = Config::default();
+    if !path.exists() {
+      return Ok(config);
+    }
+
+    ::prompt::print_debug(&format!("Loading config file {:?}", path));
+    let mut data = String::new();
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut data)) {
+      return Err(format!("error while reading config file {:?}: {}", path, e));
+    }
+
+    for (lineno, line) in data.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      match line.find('=') {
+        Some(i) => config.entries.push((
+            line[..i].trim().to_owned(),
+            line[i + 1..].trim().to_owned())),
+        None => return Err(format!("invalid config file {:?} (line {})", path, lineno + 1)),
+      };
+    }
+
+    return Ok(config);
+  }
+
+  pub fn get(self: &Self, key: &str) -> Option<String> {
+    return self
+        .entries
+        .iter()
+        .rev()
+        .find(|&&(ref k, _)| k == key)
+        .map(|&(_, ref v)| v.to_owned());
+  }
+
+  pub fn get_all(self: &Self, key: &str) -> Vec<String> {
+    return self
+        .entries
+        .iter()
+        .filter(|&&(ref k, _)| k == key)
+        .map(|&(_, ref v)| v.to_owned())
+        .collect();
+  }
+
+}
+
+/**
+ * Parse the command line flags of an operation, filling in defaults for any
+ * option that was not given on the command line. The defaults are taken, in
+ * order of precedence, from 'IC_<OPTION>' environment variables, the config
+ * file in the index directory and the user-level config file. In the config
+ * files, a key of the form '<command>.<option>' takes precedence over a plain
+ * '<option>' key. Flags without a value are enabled with 'on' or 'true'.
+ *
+ * The data and index directory can not be set in the config file in the index
+ * directory, as that file is only found through them.
+ */
+pub fn parse_flags(
+    command: &str,
+    flag_cfg: &getopts::Options,
+    args: &Vec<String>) -> Result<getopts::Matches, ::Error> {
+  let flags = match flag_cfg.parse(args) {
+    Ok(f) => f,
+    Err(e) => return Err(e.to_string()),
+  };
+
+  let user_config = match user_config_path() {
+    Some(path) => Config::load(&path)?,
+    None => Config::default(),
+  };
+
+  let lookup_early = |key: &str| -> Option<String> {
+    return flags
+        .opt_str(key)
+        .or(lookup_env(command, key))
+        .or(user_config.get(&format!("{}.{}", command, key)))
+        .or(user_config.get(key));
+  };
+
+  let repo_config = if flags.opt_defined("index_dir") {
+    let data_path = lookup_early("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+    let index_path = lookup_early("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+    Config::load(
+        &Path::new(&data_path)
+            .join(&index_path)
+            .join(::index::CONFIG_FILENAME))?
+  } else {
+    Config::default()
+  };
+
+  for &(ref key, _) in &repo_config.entries {
+    if LOCATION_OPTIONS.contains(&key.split('.').last().unwrap_or("")) {
+      return Err(format!("'{}' can not be set in the config file in the index directory", key));
+    }
+  }
+
+  let mut keys = Vec::<String>::new();
+  for config in &[&repo_config, &user_config] {
+    for &(ref key, _) in &config.entries {
+      let key = match key.find('.') {
+        Some(i) if &key[..i] == command => key[i + 1..].to_owned(),
+        Some(_) => continue,
+        None => key.to_owned(),
+      };
+
+      if !keys.contains(&key) {
+        keys.push(key);
+      }
+    }
+  }
+
+  for (name, _) in env::vars() {
+    if name.starts_with(ENV_PREFIX) {
+      let key = name[ENV_PREFIX.len()..].to_lowercase();
+      if !keys.contains(&key) {
+        keys.push(key);
+      }
+    }
+  }
+
+  let mut args_merged = Vec::<String>::new();
+  for key in keys {
+    if key.len() < 2 || !flags.opt_defined(&key) || flags.opt_present(&key) {
+      continue;
+    }
+
+    let values = match lookup_env(command, &key) {
+      Some(v) => vec!(v),
+      None => {
+        let command_key = format!("{}.{}", command, key);
+        let plain_keys = if plain_key_applies(command, &key) {
+          vec!(&repo_config, &user_config)
+        } else {
+          vec!()
+        };
+
+        [&repo_config, &user_config]
+            .iter()
+            .map(|c| c.get_all(&command_key))
+            .chain(plain_keys.iter().map(|c| c.get_all(&key)))
+            .find(|v| v.len() > 0)
+            .unwrap_or(vec!())
+      }
+    };
+
+    if values.len() > 0 {
+      args_merged.extend(default_args(flag_cfg, &key, &values)?);
+    }
+  }
+
+  args_merged.extend(args.iter().cloned());
+
+  return match flag_cfg.parse(&args_merged) {
+    Ok(f) => Ok(f),
+    Err(e) => Err(e.to_string()),
+  };
+}
+
+/**
+ * Convert default values for an option into command line arguments. Whether
+ * the option takes a value and may be repeated is found out by test parsing
+ */
+fn default_args(
+    flag_cfg: &getopts::Options,
+    key: &str,
+    values: &Vec<String>) -> Result<Vec<String>, ::Error> {
+  let args : Vec<String> = values
+      .iter()
+      .map(|v| format!("--{}={}", key, v))
+      .collect();
+
+  return match flag_cfg.parse(&args) {
+    Ok(_) => Ok(args),
+    Err(getopts::Fail::OptionDuplicated(_)) => Ok(args[args.len() - 1..].to_vec()),
+    Err(getopts::Fail::UnexpectedArgument(_)) => {
+      match values[values.len() - 1].as_str() {
+        "on" | "true" | "yes" | "1" => Ok(vec!(format!("--{}", key))),
+        "off" | "false" | "no" | "0" => Ok(vec!()),
+        v => Err(format!("invalid value for flag '{}': {}", key, v)),
+      }
+    },
+    Err(e) => Err(format!("invalid default for option '{}': {}", key, e)),
+  };
+}
+
+fn plain_key_applies(command: &str, key: &str) -> bool {
+  return match SCOPED_OPTIONS.iter().find(|&&(k, _)| k == key) {
+    Some(&(_, commands)) => commands.contains(&command),
+    None => true,
+  };
+}
+
+fn lookup_env(command: &str, key: &str) -> Option<String> {
+  if !plain_key_applies(command, key) {
+    return None;
+  }
+
+  return env::var(env_name(key)).ok();
+}
+
+fn env_name(key: &str) -> String {
+  return format!("{}{}", ENV_PREFIX, key.to_uppercase());
+}
+
+fn user_config_path() -> Option<PathBuf> {
+  if let Ok(path) = env::var("XDG_CONFIG_HOME") {
+    return Some(Path::new(&path).join("integritycheck").join("config"));
+  }
+
+  return env::var("HOME")
+      .ok()
+      .map(|path| Path::new(&path).join(".config").join("integritycheck").join("config"));
+}
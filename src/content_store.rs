@@ -0,0 +1,125 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::fs;
+use std::io::{Read,Write};
+use std::path::Path;
+use deflate;
+use inflate;
+
+pub const CONTENT_DIRNAME : &'static str = "content";
+
+/**
+ * Store a copy of all files in the snapshot that are no larger than 'max_size'
+ * in the content directory of the index. The copies are compressed and keyed by
+ * checksum, so identical files are only stored once. Returns the number of bytes
+ * that were newly stored
+ */
+pub fn store(
+    index: &::IndexDirectory,
+    data_path: &Path,
+    snapshot: &::IndexSnapshot,
+    max_size: u64) -> Result<u64, ::Error> {
+  let content_path = index.content_path();
+  if let Err(e) = fs::create_dir_all(&content_path) {
+    return Err(format!("error while creating content directory: {}", e));
+  }
+
+  let mut bytes_stored = 0;
+  for fpath in snapshot.list() {
+    let finfo = match snapshot.get(&fpath) {
+      Some(f) => f,
+      None => continue,
+    };
+
+    let checksum = match finfo.checksum {
+      Some(ref c) if finfo.size_bytes <= max_size => c.to_owned(),
+      _ => continue,
+    };
+
+    let content_file_path = content_path.join(&checksum);
+    if !content_file_path.exists() {
+      let mut data = Vec::<u8>::new();
+      if let Err(e) = fs::File::open(data_path.join(&fpath)).and_then(|mut f| f.read_to_end(&mut data)) {
+        return Err(format!("error while reading {:?}: {}", fpath, e));
+      }
+
+      if ::checksum::compute(snapshot.checksum_function.to_owned(), &data) != checksum {
+        ::prompt::print_warning(&format!("not storing {:?}; the file changed while reading", fpath));
+        continue;
+      }
+
+      ::prompt::print_debug(&format!("Storing contents of {:?}", fpath));
+      let data_compressed = deflate::deflate_bytes_zlib(&data);
+      let content_file_path_tmp = content_file_path.with_extension("tmp");
+      let result =
+          fs::File::create(&content_file_path_tmp)
+          .and_then(|mut f| f.write_all(&data_compressed))
+          .and_then(|_| fs::rename(&content_file_path_tmp, &content_file_path));
+
+      if let Err(e) = result {
+        return Err(format!("error while writing content file: {}", e));
+      }
+
+      bytes_stored += data_compressed.len() as u64;
+    }
+  }
+
+  return Ok(bytes_stored);
+}
+
+/**
+ * Load the stored contents of a file and check them against the checksum
+ */
+pub fn load(
+    index: &::IndexDirectory,
+    checksum_fn: &::checksum::ChecksumFunction,
+    checksum: &str) -> Result<Vec<u8>, ::Error> {
+  let content_file_path = index.content_path().join(checksum);
+
+  let mut data_compressed = Vec::<u8>::new();
+  let read_result =
+      fs::File::open(&content_file_path)
+      .and_then(|mut f| f.read_to_end(&mut data_compressed));
+
+  if let Err(e) = read_result {
+    return Err(format!("error while reading content file {:?}: {}", content_file_path, e));
+  }
+
+  let data = match inflate::inflate_bytes_zlib(&data_compressed) {
+    Ok(data) => data,
+    Err(e) => return Err(format!("invalid content file {:?}: {}", content_file_path, e)),
+  };
+
+  if ::checksum::compute(checksum_fn.to_owned(), &data) != checksum {
+    return Err(format!("checksum mismatch for content file: {:?}", content_file_path));
+  }
+
+  return Ok(data);
+}
+
+/**
+ * Load the stored contents of a file in a snapshot, or None if the contents were
+ * not stored. The contents are looked up by checksum, so they are found no matter
+ * which snapshot stored them
+ */
+pub fn load_file(
+    index: &::IndexDirectory,
+    snapshot: &::IndexSnapshot,
+    path: &str) -> Result<Option<Vec<u8>>, ::Error> {
+  let checksum = match snapshot.get(path) {
+    Some(&::IndexFileInfo{ checksum: Some(ref c), .. }) => c,
+    _ => return Ok(None),
+  };
+
+  if !index.content_path().join(checksum).exists() {
+    return Ok(None);
+  }
+
+  return load(index, &snapshot.checksum_function, checksum).map(|d| Some(d));
+}
@@ -0,0 +1,121 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+pub const CONFIG_KEY : &'static str = "known_bad";
+
+/**
+ * A set of known-bad checksums, e.g. the hashes of known malware. The lists are
+ * configured in the repository's config file; relative paths are resolved
+ * against the index directory:
+ *
+ *   known_bad = /var/lib/ioc/sha256.txt
+ *   known_bad = /etc/integritycheck/known-bad.txt
+ *
+ * Each line of a list contains one hex checksum of the kind used by the
+ * repository, optionally followed by further fields (so sha256sum output can be
+ * used as-is). Empty lines and lines starting with '#' are ignored.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct Denylist {
+  checksums: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DenylistMatch {
+  pub file: String,
+  pub checksum: String,
+  pub list: String,
+}
+
+impl Denylist {
+
+  pub fn load(
+      config: &::config::Config,
+      index_path: &Path,
+      checksum_function: &::checksum::ChecksumFunction) -> Result<Denylist, ::Error> {
+    let checksum_length = ::checksum::checksum_length(checksum_function);
+    let mut denylist = Denylist::default();
+
+    for list in config.get_all(CONFIG_KEY) {
+      let list_path = index_path.join(&list);
+      ::prompt::print_debug(&format!("Loading known-bad hash list {:?}", list_path));
+
+      let mut data = String::new();
+      if let Err(e) = File::open(&list_path).and_then(|mut f| f.read_to_string(&mut data)) {
+        return Err(format!("error while reading known-bad hash list {:?}: {}", list_path, e));
+      }
+
+      for line in data.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+          continue;
+        }
+
+        let checksum = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .next()
+            .unwrap_or("")
+            .trim_matches('"')
+            .to_lowercase();
+
+        if checksum.is_empty() || !checksum.chars().all(|c| c.is_digit(16)) {
+          return Err(format!("invalid checksum in known-bad hash list {:?}: {}", list_path, line));
+        }
+
+        if checksum.len() != checksum_length {
+          return Err(format!(
+              "invalid checksum in known-bad hash list {:?}: expected a {} checksum: {}",
+              list_path,
+              ::checksum::checksum_function_to_str(checksum_function),
+              line));
+        }
+
+        denylist.checksums.insert(checksum, list.to_owned());
+      }
+    }
+
+    return Ok(denylist);
+  }
+
+  pub fn is_empty(self: &Self) -> bool {
+    return self.checksums.is_empty();
+  }
+
+  /**
+   * Return all files in the snapshot whose checksum is on one of the lists
+   */
+  pub fn check(self: &Self, snapshot: &::IndexSnapshot) -> Vec<DenylistMatch> {
+    let mut matches = Vec::<DenylistMatch>::new();
+    if self.is_empty() {
+      return matches;
+    }
+
+    for (fpath, finfo) in &snapshot.files {
+      let checksum = match finfo.checksum {
+        Some(ref c) => c.to_lowercase(),
+        None => continue,
+      };
+
+      if let Some(list) = self.checksums.get(&checksum) {
+        matches.push(DenylistMatch {
+          file: fpath.to_owned(),
+          checksum: checksum.to_owned(),
+          list: list.to_owned(),
+        });
+      }
+    }
+
+    return matches;
+  }
+
+}
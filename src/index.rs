@@ -18,6 +18,8 @@ use deflate;
 const INDEX_FILENAME_PATTERN : &'static str =
     r"^(?P<timestamp>\d+)-(?P<checksum>[a-z0-9]+)\.idx$";
 
+pub const CONFIG_FILENAME : &'static str = "config";
+
 #[derive(Clone, Debug)]
 pub struct IndexReference {
   pub timestamp_us: i64,
@@ -34,7 +36,7 @@ pub struct IndexDirectory {
 pub struct IndexFileInfo {
   pub size_bytes: u64,
   pub modified_timestamp_us: Option<i64>,
-  pub checksum: Option<String>
+  pub checksum: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -74,6 +76,15 @@ impl IndexDirectory {
       };
 
       let entry_fname = entry.file_name();
+      if entry_fname == CONFIG_FILENAME {
+        continue;
+      }
+
+      /* subdirectories hold auxiliary data, e.g. the parity files */
+      if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+        continue;
+      }
+
       let pattern = Regex::new(INDEX_FILENAME_PATTERN).unwrap();
       let pattern_match = match entry_fname.to_str().and_then(|x| pattern.captures(x)) {
         Some(m) => m,
@@ -117,10 +128,47 @@ impl IndexDirectory {
     });
   }
 
+  pub fn path(self: &Self) -> &Path {
+    return &self.index_path;
+  }
+
+  pub fn config_path(self: &Self) -> PathBuf {
+    return self.index_path.join(CONFIG_FILENAME);
+  }
+
+  pub fn parity_path(self: &Self) -> PathBuf {
+    return self.index_path.join(::parity::PARITY_DIRNAME);
+  }
+
+  pub fn content_path(self: &Self) -> PathBuf {
+    return self.index_path.join(::content_store::CONTENT_DIRNAME);
+  }
+
+  pub fn verify_path(self: &Self) -> PathBuf {
+    return self.index_path.join(::verify_state::VERIFY_DIRNAME);
+  }
+
   pub fn latest(self: &Self) -> Option<IndexReference> {
     return self.index_files.get(0).cloned();
   }
 
+  /**
+   * Find a snapshot by a (possibly abbreviated) checksum
+   */
+  pub fn find(self: &Self, checksum_prefix: &str) -> Result<IndexReference, ::Error> {
+    let matches : Vec<&IndexReference> = self
+        .index_files
+        .iter()
+        .filter(|r| r.checksum.starts_with(checksum_prefix))
+        .collect();
+
+    return match matches.len() {
+      1 => Ok(matches[0].to_owned()),
+      0 => Err(format!("snapshot not found: {}", checksum_prefix)),
+      _ => Err(format!("snapshot reference is ambiguous: {}", checksum_prefix)),
+    };
+  }
+
   pub fn list(self: &Self) -> &Vec<IndexReference> {
     return &self.index_files;
   }
@@ -229,8 +277,9 @@ impl IndexSnapshot {
       }
 
       data += &format!(
-          "{} [sha256] {} {} {}\n",
+          "{} [{}] {} {} {}\n",
           encode_string(fpath),
+          ::checksum::checksum_function_to_str(&self.checksum_function),
           finfo.checksum.as_ref().unwrap_or(&"".to_owned()),
           finfo.size_bytes,
           finfo.modified_timestamp_us.unwrap_or(0));
@@ -318,7 +367,7 @@ impl IndexSnapshot {
       return Err(format!("invalid index file: {:?}", line));
     }
 
-    let checksum_function = ::checksum::checksum_function_from_str(&checksum_function)?;
+    let checksum_function = ::checksum::stored_checksum_function_from_str(&checksum_function)?;
 
     if timestamp_us != timestamp_expected_us {
       return Err(format!("timestamp does not match"));
@@ -341,7 +390,7 @@ impl IndexReference {
 
 }
 
-fn encode_string(src: &str) -> String {
+pub fn encode_string(src: &str) -> String {
   let mut dst = String::new();
 
   for c in src.chars() {
@@ -356,7 +405,7 @@ fn encode_string(src: &str) -> String {
   return dst.to_owned();
 }
 
-fn decode_string(src: &str) -> Result<String, ::Error> {
+pub fn decode_string(src: &str) -> Result<String, ::Error> {
   let mut dst = String::new();
   let mut escape = false;
   for c in src.chars() {
@@ -0,0 +1,118 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::{HashMap,HashSet};
+
+/**
+ * The result of comparing a snapshot against a set of known hashes in the style
+ * of hashdeep's audit mode. Files are matched by checksum regardless of path:
+ *
+ *   matched  the file has the same checksum as the known file at its path
+ *   moved    the checksum is known, but only at a different path
+ *   new      the checksum is not known
+ *   missing  no file has the checksum of a known file
+ */
+#[derive(Clone, Debug, Default)]
+pub struct AuditResult {
+  pub matched: Vec<String>,
+  pub moved: Vec<(String, String)>,
+  pub new: Vec<String>,
+  pub missing: Vec<String>,
+}
+
+impl AuditResult {
+
+  pub fn is_clean(self: &Self) -> bool {
+    return self.moved.len() + self.new.len() + self.missing.len() == 0;
+  }
+
+}
+
+/**
+ * Compare a snapshot against a list of known (path, checksum) pairs. Both sides
+ * are compared with index_diff::diff after stripping all metadata, so that the
+ * files are matched by path and checksum only and moved files are paired up by
+ * its rename detection
+ */
+pub fn audit(snapshot: &::IndexSnapshot, known: &Vec<(String, String)>) -> AuditResult {
+  let mut known_snapshot = ::IndexSnapshot::new(snapshot.checksum_function.to_owned());
+  let mut known_paths = HashMap::<&str, &str>::new();
+  for &(ref path, ref checksum) in known {
+    known_snapshot.update(path, &checksum_only(checksum));
+    known_paths.entry(checksum).or_insert(path);
+  }
+
+  let mut found_snapshot = ::IndexSnapshot::new(snapshot.checksum_function.to_owned());
+  for (fpath, finfo) in &snapshot.files {
+    if let Some(ref checksum) = finfo.checksum {
+      found_snapshot.update(fpath, &checksum_only(checksum));
+    }
+  }
+
+  let mut result = AuditResult::default();
+  let mut differing = HashSet::<String>::new();
+  for d in ::index_diff::diff(&known_snapshot, &found_snapshot) {
+    match d {
+      ::index_diff::IndexDiff::Renamed{from, to} => {
+        let to = to.to_string_lossy().into_owned();
+        result.moved.push((from.to_string_lossy().into_owned(), to.to_owned()));
+        differing.insert(to);
+      },
+      ::index_diff::IndexDiff::Created{file} |
+      ::index_diff::IndexDiff::Modified{file} |
+      ::index_diff::IndexDiff::MetadataModified{file} => {
+        differing.insert(file.to_string_lossy().into_owned());
+      },
+      ::index_diff::IndexDiff::Deleted{..} => (),
+    };
+  }
+
+  let mut found_checksums = HashSet::<&str>::new();
+  for (fpath, finfo) in &found_snapshot.files {
+    let checksum = match finfo.checksum {
+      Some(ref c) => c.as_str(),
+      None => continue,
+    };
+
+    found_checksums.insert(checksum);
+
+    if !differing.contains(fpath) {
+      result.matched.push(fpath.to_owned());
+      continue;
+    }
+
+    /* files that were not paired up as renames may still be copies of a known file */
+    if result.moved.iter().any(|&(_, ref to)| to == fpath) {
+      continue;
+    }
+
+    match known_paths.get(checksum) {
+      Some(path) => result.moved.push((path.to_string(), fpath.to_owned())),
+      None => result.new.push(fpath.to_owned()),
+    };
+  }
+
+  for &(ref path, ref checksum) in known {
+    if !found_checksums.contains(checksum.as_str()) {
+      result.missing.push(path.to_owned());
+    }
+  }
+
+  result.moved.sort_by(|a, b| a.1.cmp(&b.1));
+  result.missing.sort();
+  result.missing.dedup();
+  return result;
+}
+
+fn checksum_only(checksum: &str) -> ::IndexFileInfo {
+  return ::IndexFileInfo {
+    size_bytes: 0,
+    modified_timestamp_us: None,
+    checksum: Some(checksum.to_owned()),
+  };
+}
@@ -0,0 +1,89 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::HashMap;
+
+/**
+ * The result of comparing the contents of two snapshots, e.g. of an original and
+ * a replica. Unlike a diff, modification times are ignored:
+ *
+ *   missing    the file only exists in the first snapshot
+ *   extra      the file only exists in the second snapshot
+ *   differing  the file exists in both snapshots but its contents differ
+ *   renamed    the file exists in both snapshots, but under different paths
+ */
+#[derive(Clone, Debug, Default)]
+pub struct CompareResult {
+  pub matched: u64,
+  pub missing: Vec<String>,
+  pub extra: Vec<String>,
+  pub differing: Vec<String>,
+  pub renamed: Vec<(String, String)>,
+}
+
+impl CompareResult {
+
+  pub fn is_clean(self: &Self) -> bool {
+    return
+        self.missing.len() +
+        self.extra.len() +
+        self.differing.len() +
+        self.renamed.len() == 0;
+  }
+
+}
+
+pub fn compare(snapshot: &::IndexSnapshot, other: &::IndexSnapshot) -> CompareResult {
+  let mut result = CompareResult::default();
+  let mut missing = HashMap::<&str, Vec<&str>>::new();
+
+  for (fpath, finfo) in &snapshot.files {
+    let finfo_other = match other.get(fpath) {
+      Some(f) => f,
+      None => {
+        match finfo.checksum {
+          Some(ref c) => missing.entry(c.as_str()).or_insert(vec!()).push(fpath),
+          None => result.missing.push(fpath.to_owned()),
+        };
+
+        continue;
+      }
+    };
+
+    if finfo.size_bytes != finfo_other.size_bytes || finfo.checksum != finfo_other.checksum {
+      result.differing.push(fpath.to_owned());
+    } else {
+      result.matched += 1;
+    }
+  }
+
+  for (fpath, finfo) in &other.files {
+    if snapshot.get(fpath).is_some() {
+      continue;
+    }
+
+    let renamed_from = finfo
+        .checksum
+        .as_ref()
+        .and_then(|c| missing.get_mut(c.as_str()))
+        .and_then(|paths| paths.pop());
+
+    match renamed_from {
+      Some(from) => result.renamed.push((from.to_owned(), fpath.to_owned())),
+      None => result.extra.push(fpath.to_owned()),
+    };
+  }
+
+  for paths in missing.values() {
+    result.missing.extend(paths.iter().map(|p| p.to_string()));
+  }
+
+  result.missing.sort();
+  result.renamed.sort();
+  return result;
+}
@@ -31,6 +31,28 @@ pub enum IndexDiff {
   },
 }
 
+pub const DIFF_KINDS : [&'static str; 5] = [
+  "created",
+  "deleted",
+  "modified",
+  "metadata_modified",
+  "renamed",
+];
+
+impl IndexDiff {
+
+  pub fn kind_name(self: &Self) -> &'static str {
+    return match self {
+      &IndexDiff::Created{..} => "created",
+      &IndexDiff::Deleted{..} => "deleted",
+      &IndexDiff::Modified{..} => "modified",
+      &IndexDiff::MetadataModified{..} => "metadata_modified",
+      &IndexDiff::Renamed{..} => "renamed",
+    };
+  }
+
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 struct DeletedFile {
   file: PathBuf,
@@ -126,6 +148,10 @@ pub fn list_files(diffs: &IndexDiffList) -> Vec<PathBuf> {
   return files;
 }
 
+pub fn count_kind(diffs: &IndexDiffList, kind: &str) -> u64 {
+  return diffs.iter().filter(|d| d.kind_name() == kind).count() as u64;
+}
+
 // returns true if the files match and false if they dont match
 fn compare_finfo(fpath: &String, target: &::IndexFileInfo, actual: &::IndexFileInfo) -> Option<IndexDiff> {
   if target.modified_timestamp_us != actual.modified_timestamp_us {
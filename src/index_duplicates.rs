@@ -0,0 +1,73 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug)]
+pub struct DuplicateGroup {
+  pub checksum: String,
+  pub size_bytes: u64,
+  pub files: Vec<String>,
+}
+
+impl DuplicateGroup {
+
+  /**
+   * Number of bytes that could be reclaimed by keeping only a single copy
+   */
+  pub fn wasted_bytes(self: &Self) -> u64 {
+    return self.size_bytes * (self.files.len() as u64 - 1);
+  }
+
+}
+
+/**
+ * Group all files in the snapshot that share the same checksum and size. Groups
+ * are sorted by reclaimable space, largest first.
+ */
+pub fn find_duplicates(
+    snapshot: &::IndexSnapshot,
+    min_size_bytes: u64,
+    max_size_bytes: Option<u64>) -> Vec<DuplicateGroup> {
+  let mut groups = BTreeMap::<(String, u64), Vec<String>>::new();
+
+  for (fpath, finfo) in &snapshot.files {
+    if finfo.size_bytes < min_size_bytes {
+      continue;
+    }
+
+    if let Some(max_size_bytes) = max_size_bytes {
+      if finfo.size_bytes > max_size_bytes {
+        continue;
+      }
+    }
+
+    let checksum = match finfo.checksum {
+      Some(ref c) => c.to_owned(),
+      None => continue,
+    };
+
+    groups
+        .entry((checksum, finfo.size_bytes))
+        .or_insert_with(Vec::<String>::new)
+        .push(fpath.to_owned());
+  }
+
+  let mut duplicates : Vec<DuplicateGroup> = groups
+      .into_iter()
+      .filter(|&(_, ref files)| files.len() > 1)
+      .map(|((checksum, size_bytes), files)| DuplicateGroup {
+        checksum: checksum,
+        size_bytes: size_bytes,
+        files: files,
+      })
+      .collect();
+
+  duplicates.sort_by(|a, b| b.wasted_bytes().cmp(&a.wasted_bytes()));
+  return duplicates;
+}
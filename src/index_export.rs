@@ -0,0 +1,167 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::BTreeSet;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path,PathBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExportFormat {
+  SHA256Sum,
+  MD5Sum,
+  BSDTag,
+  Mtree,
+}
+
+pub fn parse_export_format(s: &str) -> Result<ExportFormat, ::Error> {
+  return match s {
+    "sha256sum" => Ok(ExportFormat::SHA256Sum),
+    "md5sum" => Ok(ExportFormat::MD5Sum),
+    "bsd-tag" => Ok(ExportFormat::BSDTag),
+    "mtree" => Ok(ExportFormat::Mtree),
+    _ => Err(format!("invalid export format: {}", s)),
+  };
+}
+
+/**
+ * Write a snapshot in the format of another checksum tool. The paths are
+ * relative to the repository root
+ */
+pub fn export(
+    snapshot: &::IndexSnapshot,
+    format: &ExportFormat,
+    data_path: &Path) -> Result<String, ::Error> {
+  let checksum_fn = ::checksum::checksum_function_to_str(&snapshot.checksum_function);
+
+  match (format, &snapshot.checksum_function) {
+    (&ExportFormat::SHA256Sum, &::checksum::ChecksumFunction::SHA256) => (),
+    (&ExportFormat::MD5Sum, &::checksum::ChecksumFunction::MD5) => (),
+    (&ExportFormat::BSDTag, _) => (),
+    (&ExportFormat::Mtree, _) => return export_mtree(snapshot, data_path),
+    _ => return Err(format!("the snapshot uses {} checksums", checksum_fn)),
+  };
+
+  let mut data = String::new();
+  for (fpath, finfo) in snapshot.files.iter() {
+    let checksum = match finfo.checksum {
+      Some(ref v) => v,
+      None => return Err(format!("missing checksum for {:?}", fpath)),
+    };
+
+    let (fpath_escaped, escaped) = escape_coreutils_path(fpath);
+    if escaped {
+      data += "\\";
+    }
+
+    data += &match format {
+      &ExportFormat::BSDTag =>
+          format!("{} ({}) = {}\n", checksum_fn.to_uppercase(), fpath_escaped, checksum),
+      _ =>
+          format!("{}  {}\n", checksum, fpath_escaped),
+    };
+  }
+
+  return Ok(data);
+}
+
+/**
+ * Escape a path the way GNU coreutils does: if the path contains a backslash or
+ * a newline, these are escaped and the line must be prefixed with a backslash
+ */
+fn escape_coreutils_path(path: &str) -> (String, bool) {
+  if !path.contains(|c| c == '\\' || c == '\n' || c == '\r') {
+    return (path.to_owned(), false);
+  }
+
+  let escaped = path
+      .replace("\\", "\\\\")
+      .replace("\n", "\\n")
+      .replace("\r", "\\r");
+
+  return (escaped, true);
+}
+
+/**
+ * Write a snapshot as a BSD mtree specification with one full path entry per
+ * file and directory. The size, time and digest are taken from the snapshot;
+ * the mode and owner are read from the files in the repository if they exist.
+ */
+fn export_mtree(snapshot: &::IndexSnapshot, data_path: &Path) -> Result<String, ::Error> {
+  let digest_keyword = match snapshot.checksum_function {
+    ::checksum::ChecksumFunction::SHA256 => "sha256digest",
+    ::checksum::ChecksumFunction::MD5 => "md5digest",
+    _ => return Err(format!("mtree does not support crc32 checksums")),
+  };
+
+  let mut directories = BTreeSet::<PathBuf>::new();
+  for fpath in snapshot.files.keys() {
+    for parent in Path::new(fpath).ancestors().skip(1) {
+      if !parent.as_os_str().is_empty() {
+        directories.insert(parent.to_path_buf());
+      }
+    }
+  }
+
+  let mut data = String::new();
+  data += "#mtree\n";
+  data += ". type=dir";
+  data += &format_mtree_owner(&data_path);
+  data += "\n";
+
+  for dpath in &directories {
+    data += &format!(
+        "./{} type=dir{}\n",
+        escape_mtree_path(&dpath.to_string_lossy()),
+        format_mtree_owner(&data_path.join(dpath)));
+  }
+
+  for (fpath, finfo) in snapshot.files.iter() {
+    let checksum = match finfo.checksum {
+      Some(ref v) => v,
+      None => return Err(format!("missing checksum for {:?}", fpath)),
+    };
+
+    data += &format!("./{} type=file", escape_mtree_path(fpath));
+    data += &format_mtree_owner(&data_path.join(fpath));
+    data += &format!(" size={}", finfo.size_bytes);
+
+    if let Some(mtime) = finfo.modified_timestamp_us {
+      data += &format!(
+          " time={}.{:09}",
+          mtime.div_euclid(1_000_000),
+          mtime.rem_euclid(1_000_000) * 1000);
+    }
+
+    data += &format!(" {}={}\n", digest_keyword, checksum);
+  }
+
+  return Ok(data);
+}
+
+fn format_mtree_owner(path: &Path) -> String {
+  return match path.symlink_metadata() {
+    Ok(m) => format!(" mode={:04o} uid={} gid={}", m.mode() & 0o7777, m.uid(), m.gid()),
+    Err(_) => String::new(),
+  };
+}
+
+/**
+ * Escape a path like mtree does (strsvis with VIS_OCTAL|VIS_WHITE|VIS_GLOB)
+ */
+fn escape_mtree_path(path: &str) -> String {
+  let mut escaped = String::new();
+
+  for b in path.bytes() {
+    match b {
+      b'!'..=b'~' if !b"#*?[\\".contains(&b) => escaped.push(b as char),
+      _ => escaped += &format!("\\{:03o}", b),
+    };
+  }
+
+  return escaped;
+}
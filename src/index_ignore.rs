@@ -0,0 +1,238 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path,PathBuf};
+use regex::{self,Regex};
+
+pub const IGNORE_FILENAME : &'static str = ".icignore";
+
+#[derive(Clone, Debug)]
+struct IgnoreRule {
+  base: PathBuf,
+  pattern: Regex,
+  negated: bool,
+  directory_only: bool,
+}
+
+/**
+ * A list of ignore rules with gitignore semantics. Rules are evaluated in the
+ * order in which they were added and the last matching rule wins.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreRules {
+  rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+
+  pub fn new() -> IgnoreRules {
+    return IgnoreRules::default();
+  }
+
+  /**
+   * Create rules from lists of exclude and include patterns (e.g. from the
+   * command line). The include patterns take precedence
+   */
+  pub fn from_patterns(
+      excludes: &Vec<String>,
+      includes: &Vec<String>) -> Result<IgnoreRules, ::Error> {
+    let mut rules = IgnoreRules::new();
+
+    for pattern in excludes {
+      rules.add_pattern(&Path::new(""), pattern)?;
+    }
+
+    for pattern in includes {
+      rules.add_pattern(&Path::new(""), &format!("!{}", pattern))?;
+    }
+
+    return Ok(rules);
+  }
+
+  /**
+   * Add a single gitignore-style pattern. The pattern is relative to the base
+   * directory, which in turn is relative to the root of the repository
+   */
+  pub fn add_pattern(self: &mut Self, base: &Path, pattern: &str) -> Result<(), ::Error> {
+    let mut pattern = pattern.trim_end();
+    if pattern.is_empty() || pattern.starts_with('#') {
+      return Ok(());
+    }
+
+    let negated = pattern.starts_with('!');
+    if negated {
+      pattern = &pattern[1..];
+    } else if pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+      pattern = &pattern[1..];
+    }
+
+    let directory_only = pattern.ends_with('/');
+    if directory_only {
+      pattern = pattern.trim_end_matches('/');
+    }
+
+    let anchored = pattern.contains('/');
+    pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+      return Ok(());
+    }
+
+    let mut regex = String::from("^");
+    if !anchored {
+      regex += "(?:.*/)?";
+    }
+
+    regex += &compile_glob(pattern);
+    regex += "$";
+
+    let regex = match Regex::new(&regex) {
+      Ok(r) => r,
+      Err(e) => return Err(format!("invalid ignore pattern {:?}: {}", pattern, e)),
+    };
+
+    self.rules.push(IgnoreRule {
+      base: base.to_path_buf(),
+      pattern: regex,
+      negated: negated,
+      directory_only: directory_only,
+    });
+
+    return Ok(());
+  }
+
+  /**
+   * Add all patterns from an ignore file if it exists
+   */
+  pub fn add_file(self: &mut Self, base: &Path, path: &Path) -> Result<(), ::Error> {
+    if !path.exists() {
+      return Ok(());
+    }
+
+    ::prompt::print_debug(&format!("Loading ignore file {:?}", path));
+    let mut data = String::new();
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut data)) {
+      return Err(format!("error while reading ignore file {:?}: {}", path, e));
+    }
+
+    for line in data.lines() {
+      self.add_pattern(base, line)?;
+    }
+
+    return Ok(());
+  }
+
+  /**
+   * Check if a path relative to the root of the repository is ignored
+   */
+  pub fn is_ignored(self: &Self, path: &Path, is_directory: bool) -> bool {
+    return self.evaluate(path, is_directory, false);
+  }
+
+  /**
+   * Apply the rules to a path that was already found to be ignored (or not) by
+   * a preceding list of rules
+   */
+  pub fn evaluate(self: &Self, path: &Path, is_directory: bool, ignored: bool) -> bool {
+    let mut ignored = ignored;
+
+    for rule in &self.rules {
+      if rule.negated != ignored || (rule.directory_only && !is_directory) {
+        continue;
+      }
+
+      let path_rel = match path.strip_prefix(&rule.base) {
+        Ok(p) => p,
+        Err(_) => continue,
+      };
+
+      let path_rel = path_rel.to_string_lossy();
+      if !path_rel.is_empty() && rule.pattern.is_match(&path_rel) {
+        ignored = !rule.negated;
+      }
+    }
+
+    return ignored;
+  }
+
+}
+
+/**
+ * Convert a glob pattern into a regular expression. '*' and '?' do not match
+ * the path separator; '**' matches any number of directories
+ */
+fn compile_glob(pattern: &str) -> String {
+  let segments : Vec<&str> = pattern.split('/').collect();
+  let mut regex = String::new();
+
+  for (i, segment) in segments.iter().enumerate() {
+    let last = i + 1 == segments.len();
+
+    if *segment == "**" {
+      regex += if last { ".+" } else { "(?:.*/)?" };
+      continue;
+    }
+
+    regex += &compile_glob_segment(segment);
+    if !last {
+      regex += "/";
+    }
+  }
+
+  return regex;
+}
+
+fn compile_glob_segment(segment: &str) -> String {
+  let mut regex = String::new();
+  let mut chars = segment.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '*' => regex += "[^/]*",
+      '?' => regex += "[^/]",
+      '\\' => {
+        if let Some(n) = chars.next() {
+          regex += &regex::escape(&n.to_string());
+        }
+      },
+      '[' => {
+        let mut raw = String::from("[");
+        let mut class = String::from("[");
+        if chars.peek() == Some(&'!') || chars.peek() == Some(&'^') {
+          raw.push(chars.next().unwrap());
+          class += "^";
+        }
+
+        let mut closed = false;
+        while let Some(n) = chars.next() {
+          raw.push(n);
+          if n == ']' {
+            closed = true;
+            break;
+          }
+
+          match n {
+            '\\' | '[' | '&' | '~' => class += &format!("\\{}", n),
+            _ => class.push(n),
+          };
+        }
+
+        if closed {
+          regex += &class;
+          regex += "]";
+        } else {
+          regex += &regex::escape(&raw);
+        }
+      },
+      _ => regex += &regex::escape(&c.to_string()),
+    };
+  }
+
+  return regex;
+}
@@ -0,0 +1,393 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Component,Path,PathBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportFormat {
+  SHA256Sum,
+  MD5Sum,
+  Hashdeep,
+  SFV,
+  Mtree,
+}
+
+/**
+ * The file paths and checksums listed in a manifest of another checksum tool
+ */
+#[derive(Clone, Debug)]
+pub struct Manifest {
+  pub checksum_function: ::checksum::ChecksumFunction,
+  pub entries: Vec<(String, String)>,
+}
+
+pub fn parse_import_format(s: &str) -> Result<ImportFormat, ::Error> {
+  return match s {
+    "sha256sum" => Ok(ImportFormat::SHA256Sum),
+    "md5sum" => Ok(ImportFormat::MD5Sum),
+    "hashdeep" => Ok(ImportFormat::Hashdeep),
+    "sfv" => Ok(ImportFormat::SFV),
+    "mtree" => Ok(ImportFormat::Mtree),
+    _ => Err(format!("invalid import format: {}", s)),
+  };
+}
+
+pub fn parse_manifest(data: &str, format: &ImportFormat) -> Result<Manifest, ::Error> {
+  return match format {
+    &ImportFormat::SHA256Sum =>
+        parse_coreutils(data, ::checksum::ChecksumFunction::SHA256, "SHA256", 64),
+    &ImportFormat::MD5Sum =>
+        parse_coreutils(data, ::checksum::ChecksumFunction::MD5, "MD5", 32),
+    &ImportFormat::Hashdeep => parse_hashdeep(data),
+    &ImportFormat::SFV => parse_sfv(data),
+    &ImportFormat::Mtree => parse_mtree(data),
+  };
+}
+
+/**
+ * Read and parse a manifest file. Use '-' to read from stdin
+ */
+pub fn read_manifest(path: &str, format: &ImportFormat) -> Result<Manifest, ::Error> {
+  let mut data = String::new();
+  let result = if path == "-" {
+    io::stdin().read_to_string(&mut data)
+  } else {
+    File::open(path).and_then(|mut f| f.read_to_string(&mut data))
+  };
+
+  if let Err(e) = result {
+    return Err(format!("error while reading manifest {:?}: {}", path, e));
+  }
+
+  return parse_manifest(&data, format);
+}
+
+/**
+ * Convert a path from a manifest into a path relative to the repository root.
+ * Absolute paths inside the repository are accepted as well
+ */
+pub fn normalize_path(path: &str, data_path_abs: &Path) -> String {
+  let path = Path::new(path);
+  let path = path.strip_prefix(data_path_abs).unwrap_or(path);
+
+  return path
+      .components()
+      .filter(|c| *c != Component::CurDir)
+      .collect::<PathBuf>()
+      .to_string_lossy()
+      .into_owned();
+}
+
+/**
+ * Parse the output of sha256sum/md5sum, including BSD-style tagged lines and
+ * lines with escaped file names
+ */
+fn parse_coreutils(
+    data: &str,
+    checksum_function: ::checksum::ChecksumFunction,
+    tag: &str,
+    checksum_len: usize) -> Result<Manifest, ::Error> {
+  let mut entries = Vec::<(String, String)>::new();
+
+  for (lineno, line) in data.lines().enumerate() {
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let (line, escaped) = if line.starts_with('\\') {
+      (&line[1..], true)
+    } else {
+      (line, false)
+    };
+
+    let tag_prefix = format!("{} (", tag);
+    let (checksum, path) = if line.starts_with(&tag_prefix) {
+      match line.rfind(") = ") {
+        Some(i) => (&line[i + 4..], &line[tag_prefix.len()..i]),
+        None => return Err(format!("invalid manifest (line {})", lineno + 1)),
+      }
+    } else if line.len() > checksum_len + 2 && line.is_char_boundary(checksum_len) {
+      match &line[checksum_len..checksum_len + 2] {
+        "  " | " *" => (&line[..checksum_len], &line[checksum_len + 2..]),
+        _ => return Err(format!("invalid manifest (line {})", lineno + 1)),
+      }
+    } else {
+      return Err(format!("invalid manifest (line {})", lineno + 1));
+    };
+
+    let path = if escaped {
+      unescape_coreutils_path(path)?
+    } else {
+      path.to_owned()
+    };
+
+    entries.push((path, parse_checksum(checksum, checksum_len, lineno)?));
+  }
+
+  return Ok(Manifest {
+    checksum_function: checksum_function,
+    entries: entries,
+  });
+}
+
+/**
+ * Parse a hashdeep audit file. The strongest supported hash that is listed in
+ * the header is used
+ */
+fn parse_hashdeep(data: &str) -> Result<Manifest, ::Error> {
+  let mut columns = Vec::<String>::new();
+  let mut entries = Vec::<(String, String)>::new();
+  let mut checksum_column = None;
+  let mut checksum_function = ::checksum::ChecksumFunction::SHA256;
+
+  for (lineno, line) in data.lines().enumerate() {
+    if line.starts_with("%%%% HASHDEEP") || line.starts_with("##") || line.is_empty() {
+      continue;
+    }
+
+    if line.starts_with("%%%% ") {
+      columns = line[5..].split(',').map(|c| c.trim().to_owned()).collect();
+      checksum_column = columns.iter().position(|c| c == "sha256");
+      checksum_function = ::checksum::ChecksumFunction::SHA256;
+
+      if checksum_column.is_none() {
+        checksum_column = columns.iter().position(|c| c == "md5");
+        checksum_function = ::checksum::ChecksumFunction::MD5;
+      }
+
+      if checksum_column.is_none() || columns.last() != Some(&"filename".to_owned()) {
+        return Err(format!("unsupported hashdeep columns: {}", &line[5..]));
+      }
+
+      continue;
+    }
+
+    let checksum_column = match checksum_column {
+      Some(c) => c,
+      None => return Err(format!("invalid hashdeep file: missing header")),
+    };
+
+    let fields : Vec<&str> = line.splitn(columns.len(), ',').collect();
+    if fields.len() != columns.len() {
+      return Err(format!("invalid manifest (line {})", lineno + 1));
+    }
+
+    let checksum_len = match checksum_function {
+      ::checksum::ChecksumFunction::SHA256 => 64,
+      _ => 32,
+    };
+
+    entries.push((
+        fields[fields.len() - 1].to_owned(),
+        parse_checksum(fields[checksum_column], checksum_len, lineno)?));
+  }
+
+  return Ok(Manifest {
+    checksum_function: checksum_function,
+    entries: entries,
+  });
+}
+
+/**
+ * Parse a simple file verification (SFV) file with CRC-32 checksums
+ */
+fn parse_sfv(data: &str) -> Result<Manifest, ::Error> {
+  let mut entries = Vec::<(String, String)>::new();
+
+  for (lineno, line) in data.lines().enumerate() {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with(';') {
+      continue;
+    }
+
+    match line.rfind(' ') {
+      Some(i) => entries.push((
+          line[..i].trim_end().to_owned(),
+          parse_checksum(&line[i + 1..], 8, lineno)?)),
+      None => return Err(format!("invalid manifest (line {})", lineno + 1)),
+    };
+  }
+
+  return Ok(Manifest {
+    checksum_function: ::checksum::ChecksumFunction::CRC32,
+    entries: entries,
+  });
+}
+
+/**
+ * Parse a BSD mtree specification. Both full path entries and the classic
+ * format with relative names and '..' are supported, as well as the '/set'
+ * and '/unset' directives. Only entries of type 'file' are imported
+ */
+fn parse_mtree(data: &str) -> Result<Manifest, ::Error> {
+  let mut entries = Vec::<(String, String, String)>::new();
+  let mut defaults = HashMap::<String, String>::new();
+  let mut cwd = PathBuf::new();
+  let mut line_buf = String::new();
+
+  for (lineno, line) in data.lines().enumerate() {
+    if line.ends_with('\\') {
+      line_buf += &line[..line.len() - 1];
+      line_buf += " ";
+      continue;
+    }
+
+    line_buf += line;
+    let line = line_buf.trim().to_owned();
+    line_buf.clear();
+
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or("");
+    let mut keywords = HashMap::<String, String>::new();
+    for word in words {
+      match word.find('=') {
+        Some(i) => keywords.insert(word[..i].to_owned(), word[i + 1..].to_owned()),
+        None => keywords.insert(word.to_owned(), String::new()),
+      };
+    }
+
+    match name {
+      "/set" => {
+        defaults.extend(keywords);
+        continue;
+      },
+      "/unset" => {
+        for key in keywords.keys() {
+          if key == "all" {
+            defaults.clear();
+          } else {
+            defaults.remove(key);
+          }
+        }
+
+        continue;
+      },
+      ".." => {
+        cwd.pop();
+        continue;
+      },
+      _ => (),
+    };
+
+    let name = unescape_mtree_path(name)?;
+    let full_path = name.contains('/');
+    let path = if full_path {
+      PathBuf::from(&name)
+    } else {
+      cwd.join(&name)
+    };
+
+    let keyword = |key: &str| keywords.get(key).or(defaults.get(key)).cloned();
+    let entry_type = keyword("type").unwrap_or("file".into());
+
+    if entry_type == "dir" {
+      if !full_path && name != "." {
+        cwd = path;
+      }
+
+      continue;
+    }
+
+    if entry_type != "file" {
+      continue;
+    }
+
+    let path = path
+        .strip_prefix(".")
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .into_owned();
+
+    let (checksum_fn, checksum) = if let Some(c) = keyword("sha256digest").or(keyword("sha256")) {
+      ("sha256".to_owned(), parse_checksum(&c, 64, lineno)?)
+    } else if let Some(c) = keyword("md5digest").or(keyword("md5")) {
+      ("md5".to_owned(), parse_checksum(&c, 32, lineno)?)
+    } else {
+      return Err(format!("missing sha256digest or md5digest for {:?} (line {})", path, lineno + 1));
+    };
+
+    entries.push((path, checksum_fn, checksum));
+  }
+
+  let checksum_fn = if entries.iter().all(|&(_, ref f, _)| f == "sha256") {
+    "sha256"
+  } else if entries.iter().all(|&(_, ref f, _)| f == "md5") {
+    "md5"
+  } else {
+    return Err(format!("all files must have the same digest type"));
+  };
+
+  return Ok(Manifest {
+    checksum_function: ::checksum::checksum_function_from_str(checksum_fn)?,
+    entries: entries.into_iter().map(|(p, _, c)| (p, c)).collect(),
+  });
+}
+
+fn unescape_mtree_path(path: &str) -> Result<String, ::Error> {
+  let mut bytes = Vec::<u8>::new();
+  let mut src = path.bytes();
+
+  while let Some(b) = src.next() {
+    if b != b'\\' {
+      bytes.push(b);
+      continue;
+    }
+
+    let octal : Vec<u8> = src.by_ref().take(3).collect();
+    let value = String::from_utf8(octal)
+        .ok()
+        .and_then(|v| u8::from_str_radix(&v, 8).ok());
+
+    match value {
+      Some(v) => bytes.push(v),
+      None => return Err(format!("invalid escape sequence in path {:?}", path)),
+    };
+  }
+
+  return match String::from_utf8(bytes) {
+    Ok(v) => Ok(v),
+    Err(_) => Err(format!("invalid path: {:?}", path)),
+  };
+}
+
+fn parse_checksum(checksum: &str, len: usize, lineno: usize) -> Result<String, ::Error> {
+  if checksum.len() != len || !checksum.chars().all(|c| c.is_digit(16)) {
+    return Err(format!("invalid checksum {:?} (line {})", checksum, lineno + 1));
+  }
+
+  return Ok(checksum.to_lowercase());
+}
+
+fn unescape_coreutils_path(path: &str) -> Result<String, ::Error> {
+  let mut dst = String::new();
+  let mut chars = path.chars();
+
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      dst.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      Some('\\') => dst.push('\\'),
+      Some('n') => dst.push('\n'),
+      Some('r') => dst.push('\r'),
+      _ => return Err(format!("invalid escape sequence in path {:?}", path)),
+    };
+  }
+
+  return Ok(dst);
+}
@@ -0,0 +1,91 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::{HashMap,HashSet};
+
+/**
+ * The copies of damaged files found in a backup. Each entry in 'found' is a pair
+ * of the path in the repository and the path of a file in the backup with the
+ * expected checksum.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct RecoverResult {
+  pub found: Vec<(String, String)>,
+  pub not_found: Vec<String>,
+}
+
+/**
+ * Return a copy of the backup snapshot that only contains the files which could
+ * be a copy of one of the target files, i.e. which have the same size. This
+ * avoids computing the checksums of all files in the backup
+ */
+pub fn candidates(
+    snapshot: &::IndexSnapshot,
+    targets: &Vec<String>,
+    backup: &::IndexSnapshot) -> ::IndexSnapshot {
+  let sizes : HashSet<u64> = targets
+      .iter()
+      .filter_map(|p| snapshot.get(p))
+      .map(|finfo| finfo.size_bytes)
+      .collect();
+
+  let mut candidates = ::IndexSnapshot::new(backup.checksum_function.to_owned());
+  for (fpath, finfo) in &backup.files {
+    if sizes.contains(&finfo.size_bytes) {
+      candidates.update(fpath, finfo);
+    }
+  }
+
+  return candidates;
+}
+
+/**
+ * Find a copy of each target file in the backup. A file at the same path is
+ * preferred; otherwise any file with the expected checksum is used
+ */
+pub fn locate(
+    snapshot: &::IndexSnapshot,
+    targets: &Vec<String>,
+    backup: &::IndexSnapshot) -> RecoverResult {
+  let mut result = RecoverResult::default();
+
+  let mut backup_checksums = HashMap::<&str, &str>::new();
+  for (fpath, finfo) in &backup.files {
+    if let Some(ref checksum) = finfo.checksum {
+      backup_checksums.entry(checksum).or_insert(fpath);
+    }
+  }
+
+  for target in targets {
+    let checksum = match snapshot.get(target).and_then(|f| f.checksum.as_ref()) {
+      Some(c) => c,
+      None => {
+        result.not_found.push(target.to_owned());
+        continue;
+      }
+    };
+
+    let same_path = backup
+        .get(target)
+        .and_then(|f| f.checksum.as_ref())
+        .map(|c| c == checksum)
+        .unwrap_or(false);
+
+    if same_path {
+      result.found.push((target.to_owned(), target.to_owned()));
+      continue;
+    }
+
+    match backup_checksums.get(checksum.as_str()) {
+      Some(source) => result.found.push((target.to_owned(), source.to_string())),
+      None => result.not_found.push(target.to_owned()),
+    };
+  }
+
+  return result;
+}
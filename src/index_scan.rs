@@ -8,12 +8,13 @@
  */
 use std::fs;
 use std::path::{Path,PathBuf};
-use std::time::UNIX_EPOCH;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use walkdir::WalkDir;
 
 pub struct ScanOptions {
-  pub exclude_paths: Vec<PathBuf>,
-  pub exclusive_paths: Option<Vec<PathBuf>>
+  pub exclude_paths: ::path_trie::PathTrie,
+  pub exclusive_paths: Option<::path_trie::PathTrie>,
+  pub ignore_rules: ::index_ignore::IgnoreRules,
 }
 
 pub fn scan_metadata(
@@ -29,12 +30,54 @@ pub fn scan_metadata(
     Err(e) => return Err(e.to_string()),
   };
 
-  for entry in WalkDir::new(Path::new(&data_path)) {
+  // rules from the .icignore files are loaded while walking the tree; the rules
+  // from the scan options are applied last so that they take precedence
+  let mut ignore_rules = ::index_ignore::IgnoreRules::new();
+  ignore_rules.add_file(
+      &Path::new(""),
+      &data_path.join(::index_ignore::IGNORE_FILENAME))?;
+
+  let mut walk = WalkDir::new(Path::new(&data_path)).into_iter();
+  while let Some(entry) = walk.next() {
     let entry = match entry {
       Ok(v) => v,
       Err(e) => return Err(e.to_string()),
     };
 
+    if entry.depth() > 0 {
+      let entry_path_rel = match entry.path().strip_prefix(&data_path) {
+        Ok(v) => v,
+        Err(e) => return Err(e.to_string()),
+      };
+
+      let is_dir = entry.file_type().is_dir();
+      if is_dir && !check_excludes_dir(&entry_path_rel, opts) {
+        ::prompt::print_debug(&format!("Skipping directory: {:?}", entry_path_rel));
+        walk.skip_current_dir();
+        continue;
+      }
+
+      let ignored = opts.ignore_rules.evaluate(
+          &entry_path_rel,
+          is_dir,
+          ignore_rules.is_ignored(&entry_path_rel, is_dir));
+
+      if ignored {
+        ::prompt::print_debug(&format!("Ignoring path: {:?}", entry_path_rel));
+        if is_dir {
+          walk.skip_current_dir();
+        }
+
+        continue;
+      }
+
+      if is_dir {
+        ignore_rules.add_file(
+            &entry_path_rel,
+            &entry.path().join(::index_ignore::IGNORE_FILENAME))?;
+      }
+    }
+
     let entry_meta = match entry.metadata() {
       Ok(v) => v,
       Err(e) => return Err(e.to_string()),
@@ -44,11 +87,7 @@ pub fn scan_metadata(
       continue;
     }
 
-    let entry_mtime_ms = entry_meta
-        .modified()
-        .and_then(|x| Ok(x.duration_since(UNIX_EPOCH).unwrap()))
-        .ok()
-        .map(|v| v.as_secs() as i64 * 1_000_000 + v.subsec_nanos() as i64 / 1_000);
+    let entry_mtime_ms = modified_timestamp_us(&entry_meta);
 
     let entry_path = match fs::canonicalize(entry.path()) {
       Ok(e) => e,
@@ -74,7 +113,7 @@ pub fn scan_metadata(
     index.update(entry_path, &::IndexFileInfo {
       size_bytes: entry_meta.len(),
       modified_timestamp_us: entry_mtime_ms,
-      checksum: None
+      checksum: None,
     });
 
     stats_files_scanned += 1;
@@ -92,11 +131,74 @@ pub fn scan_metadata(
   return Ok(index);
 }
 
+/**
+ * Read the metadata of an explicit list of files. The files are added to the
+ * snapshot using their paths as given
+ */
+pub fn scan_metadata_list(
+    paths: &Vec<PathBuf>,
+    index: ::IndexSnapshot) -> Result<::IndexSnapshot, ::Error> {
+  let mut index = index;
+  let mut stats_files_scanned = 0;
+  let mut stats_bytes_scanned = 0;
+
+  for path in paths {
+    let meta = match fs::metadata(path) {
+      Ok(v) => v,
+      Err(e) => return Err(format!("error while reading {:?}: {}", path, e)),
+    };
+
+    if !meta.is_file() {
+      return Err(format!("not a regular file: {:?}", path));
+    }
+
+    let path_str = match path.to_str() {
+      Some(v) => v,
+      None => return Err(format!("invalid path")),
+    };
+
+    ::prompt::print_debug(&format!("Reading file metadata: {:?}", path_str));
+    index.update(path_str, &::IndexFileInfo {
+      size_bytes: meta.len(),
+      modified_timestamp_us: modified_timestamp_us(&meta),
+      checksum: None,
+    });
+
+    stats_files_scanned += 1;
+    stats_bytes_scanned += meta.len();
+
+    ::prompt::print_scanprogress(
+        stats_files_scanned,
+        stats_bytes_scanned,
+        0,
+        0);
+  }
+
+  ::prompt::print_scanprogress_complete();
+
+  return Ok(index);
+}
+
 pub fn scan_checksums(
     data_path: &Path,
     index: ::IndexSnapshot,
     opts: &ScanOptions) -> Result<::IndexSnapshot, ::Error> {
+  return scan_checksums_checkpointed(data_path, index, opts, None, |_| Ok(()));
+}
+
+/**
+ * Compute the missing checksums like scan_checksums. Every 'checkpoint_interval',
+ * the snapshot including all checksums computed so far is passed to 'checkpoint'
+ * so that an interrupted scan can be resumed
+ */
+pub fn scan_checksums_checkpointed<F: FnMut(&::IndexSnapshot) -> Result<(), ::Error>>(
+    data_path: &Path,
+    index: ::IndexSnapshot,
+    opts: &ScanOptions,
+    checkpoint_interval: Option<Duration>,
+    mut checkpoint: F) -> Result<::IndexSnapshot, ::Error> {
   let mut index = index;
+  let mut checkpoint_time = Instant::now();
   let mut stats_files_scanned = 0;
   let mut stats_bytes_scanned = 0;
   let stats_files_total = index.total_file_count();
@@ -139,6 +241,11 @@ pub fn scan_checksums(
         stats_bytes_scanned,
         stats_files_total,
         stats_bytes_total);
+
+    if checkpoint_interval.map(|i| checkpoint_time.elapsed() >= i).unwrap_or(false) {
+      checkpoint(&index)?;
+      checkpoint_time = Instant::now();
+    }
   }
 
   ::prompt::print_scanprogress_complete();
@@ -146,6 +253,77 @@ pub fn scan_checksums(
   return Ok(index)
 }
 
+/**
+ * Recompute the checksums of the given files in order, e.g. to verify files
+ * whose checksums were copied from a snapshot, until 'deadline' has passed.
+ * Returns the paths of the files that were scanned
+ */
+pub fn rescan_checksums(
+    data_path: &Path,
+    index: ::IndexSnapshot,
+    paths: &[String],
+    deadline: Option<Instant>) -> Result<(::IndexSnapshot, Vec<String>), ::Error> {
+  let mut index = index;
+  let mut paths_scanned = Vec::<String>::new();
+  let mut stats_bytes_scanned = 0;
+  let stats_files_total = paths.len() as u64;
+  let stats_bytes_total = paths
+      .iter()
+      .filter_map(|p| index.get(p))
+      .fold(0, |acc, finfo| acc + finfo.size_bytes);
+
+  for file_path in paths {
+    if deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
+      ::prompt::print_debug("Time budget exhausted; stopping checksum calculation");
+      break;
+    }
+
+    let mut file_info = match &index.get(&file_path) {
+      &Some(v) => v.to_owned(),
+      &None => return Err(format!("invalid path")),
+    };
+
+    ::prompt::print_debug(&format!("Computing checksum for {:?}", file_path));
+
+    file_info.checksum = Some(::checksum::compute_file(
+        index.checksum_function.clone(),
+        &data_path.join(&file_path))?);
+
+    index.update(&file_path, &file_info);
+    paths_scanned.push(file_path.to_owned());
+    stats_bytes_scanned += file_info.size_bytes;
+
+    ::prompt::print_scanprogress(
+        paths_scanned.len() as u64,
+        stats_bytes_scanned,
+        stats_files_total,
+        stats_bytes_total);
+  }
+
+  ::prompt::print_scanprogress_complete();
+
+  return Ok((index, paths_scanned))
+}
+
+fn modified_timestamp_us(meta: &fs::Metadata) -> Option<i64> {
+  return meta
+      .modified()
+      .and_then(|x| Ok(x.duration_since(UNIX_EPOCH).unwrap()))
+      .ok()
+      .map(|v| v.as_secs() as i64 * 1_000_000 + v.subsec_nanos() as i64 / 1_000);
+}
+
+/**
+ * Total size of all files for which a checksum still needs to be computed
+ */
+pub fn pending_size_bytes(index: &::IndexSnapshot) -> u64 {
+  return index
+      .files
+      .iter()
+      .filter(|&(_, finfo)| finfo.checksum.is_none())
+      .fold(0, |acc, (_, finfo)| acc + finfo.size_bytes);
+}
+
 /**
  * Copy all file checksums from another snapshot
  */
@@ -178,29 +356,34 @@ pub fn copy_checksums(
   return Ok(index);
 }
 
-// FIXME: this should not be linear
 fn check_excludes(path: &Path, opts: &ScanOptions) -> bool {
-  {
-    let skip = opts
-        .exclude_paths
-        .iter()
-        .any(|p| path.starts_with(p));
+  if opts.exclude_paths.contains_prefix_of(path) {
+    return false;
+  }
 
-    if skip {
+  if let Some(ref exclusive_paths) = opts.exclusive_paths {
+    if !exclusive_paths.contains_prefix_of(path) {
       return false;
     }
   }
 
-  if let Some(ref exclusive_paths) = opts.exclusive_paths {
-    let skip = !exclusive_paths
-        .iter()
-        .any(|p| path.starts_with(p));
+  return true;
+}
 
-    if skip {
+/**
+ * Check if a directory may contain files that are not excluded. Directories
+ * for which this returns false are not descended into
+ */
+fn check_excludes_dir(path: &Path, opts: &ScanOptions) -> bool {
+  if opts.exclude_paths.contains_prefix_of(path) {
+    return false;
+  }
+
+  if let Some(ref exclusive_paths) = opts.exclusive_paths {
+    if !exclusive_paths.intersects(path) {
       return false;
     }
   }
 
   return true;
 }
-
@@ -0,0 +1,61 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::path::{Path,PathBuf};
+use std::collections::BTreeMap;
+
+pub type DirectoryUsageMap = BTreeMap<PathBuf, DirectoryUsage>;
+
+#[derive(Clone, Debug, Default)]
+pub struct DirectoryUsage {
+  pub size_bytes: u64,
+  pub file_count: u64,
+}
+
+/**
+ * Sum up the size and number of files below each directory in the snapshot.
+ * Only files under `prefix` are considered and only directories up to `depth`
+ * levels below the prefix are reported.
+ */
+pub fn aggregate(
+    snapshot: &::IndexSnapshot,
+    prefix: &Path,
+    depth: Option<usize>) -> DirectoryUsageMap {
+  let mut usage = DirectoryUsageMap::new();
+
+  for (fpath, finfo) in &snapshot.files {
+    let fpath = Path::new(fpath);
+    let fpath_rel = match fpath.strip_prefix(prefix) {
+      Ok(p) => p,
+      Err(_) => continue,
+    };
+
+    let mut dir = prefix.to_path_buf();
+    let mut dir_components = fpath_rel.parent().map(|p| p.components());
+    let mut dir_depth = 0;
+
+    loop {
+      let entry = usage.entry(dir.to_owned()).or_insert_with(DirectoryUsage::default);
+      entry.size_bytes += finfo.size_bytes;
+      entry.file_count += 1;
+
+      if depth.map(|d| dir_depth >= d).unwrap_or(false) {
+        break;
+      }
+
+      match dir_components.as_mut().and_then(|c| c.next()) {
+        Some(c) => dir.push(c.as_os_str()),
+        None => break,
+      }
+
+      dir_depth += 1;
+    }
+  }
+
+  return usage;
+}
@@ -0,0 +1,172 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+
+/**
+ * Encode a string as a quoted JSON string literal
+ */
+pub fn encode_string(src: &str) -> String {
+  let mut dst = String::new();
+  dst.push('"');
+
+  for c in src.chars() {
+    match c {
+      '"' => dst += "\\\"",
+      '\\' => dst += "\\\\",
+      '\n' => dst += "\\n",
+      '\r' => dst += "\\r",
+      '\t' => dst += "\\t",
+      c if (c as u32) < 0x20 => dst += &format!("\\u{:04x}", c as u32),
+      _ => dst.push(c),
+    }
+  }
+
+  dst.push('"');
+  return dst;
+}
+
+/**
+ * Encode an optional string as a JSON string literal or null
+ */
+pub fn encode_option(src: &Option<String>) -> String {
+  return match src {
+    &Some(ref s) => encode_string(s),
+    &None => "null".into(),
+  };
+}
+
+/**
+ * Encode a list of already encoded JSON values as a JSON array
+ */
+pub fn encode_list(values: &[String]) -> String {
+  return format!("[{}]", values.join(","));
+}
+
+/**
+ * Encode a list of keys and already encoded JSON values as a JSON object
+ */
+pub fn encode_object(fields: &[(&str, String)]) -> String {
+  let fields : Vec<String> = fields
+      .iter()
+      .map(|&(ref k, ref v)| format!("{}:{}", encode_string(k), v))
+      .collect();
+
+  return format!("{{{}}}", fields.join(","));
+}
+
+pub fn encode_snapshot_ref(snap_ref: &::IndexReference) -> String {
+  return encode_object(&[
+    ("checksum", encode_string(&snap_ref.checksum)),
+    ("timestamp_us", snap_ref.timestamp_us.to_string()),
+  ]);
+}
+
+pub fn diff_fields(diff: &::index_diff::IndexDiff) -> Vec<(&'static str, String)> {
+  let kind = ("kind", encode_string(diff.kind_name()));
+
+  return match diff {
+    &::index_diff::IndexDiff::Renamed{ref from, ref to} =>
+      vec!(
+        kind,
+        ("from", encode_string(&from.to_string_lossy())),
+        ("to", encode_string(&to.to_string_lossy())),
+      ),
+    &::index_diff::IndexDiff::Created{ref file} |
+    &::index_diff::IndexDiff::Deleted{ref file} |
+    &::index_diff::IndexDiff::Modified{ref file} |
+    &::index_diff::IndexDiff::MetadataModified{ref file} =>
+      vec!(
+        kind,
+        ("path", encode_string(&file.to_string_lossy())),
+      ),
+  };
+}
+
+pub fn encode_diff_counts(diffs: &::index_diff::IndexDiffList) -> String {
+  let counts : Vec<(&str, String)> = ::index_diff::DIFF_KINDS
+      .iter()
+      .map(|k| (*k, ::index_diff::count_kind(diffs, k).to_string()))
+      .collect();
+
+  return encode_object(&counts);
+}
+
+pub fn encode_known_bad(matches: &Vec<::denylist::DenylistMatch>) -> String {
+  let matches : Vec<String> = matches
+      .iter()
+      .map(|m| encode_object(&vec!(
+          ("path", encode_string(&m.file)),
+          ("checksum", encode_string(&m.checksum)),
+          ("list", encode_string(&m.list)))))
+      .collect();
+
+  return encode_list(&matches);
+}
+
+pub fn file_fields(fpath: &str, finfo: &::IndexFileInfo) -> Vec<(&'static str, String)> {
+  let mtime = match finfo.modified_timestamp_us {
+    Some(t) => t.to_string(),
+    None => "null".into(),
+  };
+
+  return vec!(
+    ("path", encode_string(fpath)),
+    ("checksum", encode_option(&finfo.checksum)),
+    ("size_bytes", finfo.size_bytes.to_string()),
+    ("modified_timestamp_us", mtime),
+  );
+}
+
+pub fn snapshot_fields(
+    snap_ref: &::IndexReference,
+    snap: &::IndexSnapshot) -> Vec<(&'static str, String)> {
+  let checksum_function = ::checksum::checksum_function_to_str(&snap.checksum_function);
+
+  return vec!(
+    ("checksum", encode_string(&snap_ref.checksum)),
+    ("timestamp_us", snap_ref.timestamp_us.to_string()),
+    ("checksum_function", encode_string(&checksum_function)),
+    ("message", encode_option(&snap.message)),
+    ("total_files", snap.total_file_count().to_string()),
+    ("total_bytes", snap.total_size_bytes().to_string()),
+  );
+}
+
+pub fn duplicate_group_fields(
+    group: &::index_duplicates::DuplicateGroup) -> Vec<(&'static str, String)> {
+  let files : Vec<String> = group.files.iter().map(|f| encode_string(f)).collect();
+
+  return vec!(
+    ("checksum", encode_string(&group.checksum)),
+    ("size_bytes", group.size_bytes.to_string()),
+    ("wasted_bytes", group.wasted_bytes().to_string()),
+    ("files", encode_list(&files)),
+  );
+}
+
+/**
+ * Build the summary fields shared by the status, verify and ack commands
+ */
+pub fn status_fields(
+    command: &str,
+    data_path: &str,
+    snap_ref: &::IndexReference,
+    snap: &::IndexSnapshot,
+    diffs: &::index_diff::IndexDiffList) -> Vec<(&'static str, String)> {
+  let status = if diffs.len() == 0 { "clean" } else { "dirty" };
+
+  return vec!(
+    ("command", encode_string(command)),
+    ("repository", encode_string(&::prompt::format_repository_path(data_path))),
+    ("snapshot", encode_snapshot_ref(snap_ref)),
+    ("total_files", snap.total_file_count().to_string()),
+    ("total_bytes", snap.total_size_bytes().to_string()),
+    ("status", encode_string(status)),
+    ("counts", encode_diff_counts(diffs)),
+  );
+}
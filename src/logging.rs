@@ -0,0 +1,322 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::os::unix::net::UnixDatagram;
+use std::process;
+use libc;
+use time;
+
+const SYSLOG_SOCKET : &'static str = "/dev/log";
+const JOURNALD_SOCKET : &'static str = "/run/systemd/journal/socket";
+const SYSLOG_FACILITY_USER : u8 = 1;
+const SYSLOG_SD_ID_ORIGIN : &'static str = "origin";
+const SYSLOG_SD_NAME : &'static str = "integritycheck";
+const APP_NAME : &'static str = "integritycheck";
+
+const SEVERITY_CRIT : u8 = 2;
+const SEVERITY_ERR : u8 = 3;
+const SEVERITY_NOTICE : u8 = 5;
+const SEVERITY_INFO : u8 = 6;
+
+#[derive(Clone, Debug)]
+pub enum LogTarget {
+  Syslog{ socket: String, sd_id: String },
+  Journald{ socket: String },
+}
+
+struct LogRecord {
+  severity: u8,
+  message: String,
+  fields: Vec<(&'static str, String)>,
+}
+
+/**
+ * Parse a comma-separated list of log targets. Each target is either 'syslog'
+ * or 'journald', optionally followed by a colon and the path of the socket. The
+ * 'syslog' target needs the IANA private enterprise number that identifies the
+ * structured data of the messages
+ */
+pub fn parse_targets(
+    spec: &str,
+    enterprise_number: Option<String>) -> Result<Vec<LogTarget>, ::Error> {
+  let mut targets = Vec::<LogTarget>::new();
+
+  for entry in spec.split(',').filter(|e| !e.is_empty()) {
+    let (name, socket) = match entry.find(':') {
+      Some(i) => (&entry[..i], Some(entry[i + 1..].to_owned())),
+      None => (entry, None),
+    };
+
+    targets.push(match name {
+      "syslog" => LogTarget::Syslog {
+        socket: socket.unwrap_or(SYSLOG_SOCKET.into()),
+        sd_id: format!("{}@{}", SYSLOG_SD_NAME, parse_enterprise_number(&enterprise_number)?),
+      },
+      "journald" => LogTarget::Journald {
+        socket: socket.unwrap_or(JOURNALD_SOCKET.into()),
+      },
+      _ => return Err(format!("invalid log target: {}", entry)),
+    });
+  }
+
+  return Ok(targets);
+}
+
+fn parse_enterprise_number(value: &Option<String>) -> Result<String, ::Error> {
+  let value = match value {
+    &Some(ref v) => v,
+    &None => return Err(
+        "the 'syslog' log target needs a private enterprise number \
+         (--syslog_enterprise_number)".into()),
+  };
+
+  let valid = value
+      .split('.')
+      .all(|p| !p.is_empty() && p.chars().all(|c| c.is_digit(10)));
+
+  if !valid {
+    return Err(format!("invalid private enterprise number: {}", value));
+  }
+
+  return Ok(value.to_owned());
+}
+
+/**
+ * Send a summary of the run and one record per change and known-bad file to
+ * the log targets. Known-bad files are logged with severity 'crit', deleted
+ * and corrupted files with severity 'err', all other changes with severity
+ * 'notice'. The summary uses the highest severity of any record, or 'info' if
+ * there were none. Failures are reported as warnings.
+ */
+pub fn log_run(
+    targets: &Vec<LogTarget>,
+    command: &str,
+    data_path: &str,
+    snap_ref: &::IndexReference,
+    diff: &::index_diff::IndexDiffList,
+    known_bad: &Vec<::denylist::DenylistMatch>) {
+  if targets.len() == 0 {
+    return;
+  }
+
+  let repository = ::prompt::format_repository_path(data_path);
+  let mut records = Vec::<LogRecord>::new();
+
+  let mut summary_fields = vec!(
+    ("command", command.to_owned()),
+    ("repository", repository.to_owned()),
+    ("snapshot", snap_ref.checksum.to_owned()),
+    ("status", if diff.len() == 0 && known_bad.len() == 0 { "clean".into() } else { "dirty".into() }),
+  );
+
+  let mut summary_counts = Vec::<String>::new();
+  for kind in ::index_diff::DIFF_KINDS.iter() {
+    let count = ::index_diff::count_kind(diff, kind);
+    summary_fields.push((*kind, count.to_string()));
+    if count > 0 {
+      summary_counts.push(format!("{} {}", count, kind));
+    }
+  }
+
+  summary_fields.push(("known_bad", known_bad.len().to_string()));
+  if known_bad.len() > 0 {
+    summary_counts.push(format!("{} known-bad", known_bad.len()));
+  }
+
+  let summary_severity = if known_bad.len() > 0 {
+    SEVERITY_CRIT
+  } else {
+    diff.iter().map(|d| severity(d)).min().unwrap_or(SEVERITY_INFO)
+  };
+
+  records.push(LogRecord {
+    severity: summary_severity,
+    message: if summary_counts.len() == 0 {
+      format!("{} {}: clean", command, repository)
+    } else {
+      format!("{} {}: dirty, {}", command, repository, summary_counts.join(", "))
+    },
+    fields: summary_fields,
+  });
+
+  for d in ::prompt::sort_diff(diff) {
+    let mut fields = vec!(
+      ("command", command.to_owned()),
+      ("repository", repository.to_owned()),
+      ("kind", d.kind_name().to_owned()),
+    );
+
+    match d {
+      ::index_diff::IndexDiff::Renamed{ref from, ref to} => {
+        fields.push(("from", from.to_string_lossy().into_owned()));
+        fields.push(("to", to.to_string_lossy().into_owned()));
+      },
+      ::index_diff::IndexDiff::Created{ref file} |
+      ::index_diff::IndexDiff::Deleted{ref file} |
+      ::index_diff::IndexDiff::Modified{ref file} |
+      ::index_diff::IndexDiff::MetadataModified{ref file} => {
+        fields.push(("path", file.to_string_lossy().into_owned()));
+      },
+    };
+
+    records.push(LogRecord {
+      severity: severity(&d),
+      message: format!("{}: {}", repository, ::prompt::format_diff(&d).trim()),
+      fields: fields,
+    });
+  }
+
+  for m in known_bad {
+    records.push(LogRecord {
+      severity: SEVERITY_CRIT,
+      message: format!("{}: known-bad {:?} (listed in {})", repository, m.file, m.list),
+      fields: vec!(
+        ("command", command.to_owned()),
+        ("repository", repository.to_owned()),
+        ("kind", "known_bad".to_owned()),
+        ("path", m.file.to_owned()),
+        ("checksum", m.checksum.to_owned()),
+        ("list", m.list.to_owned()),
+      ),
+    });
+  }
+
+  for target in targets {
+    let result = match target {
+      &LogTarget::Syslog{ref socket, ref sd_id} => send_syslog(socket, sd_id, command, &records),
+      &LogTarget::Journald{ref socket} => send_journald(socket, &records),
+    };
+
+    if let Err(e) = result {
+      ::prompt::print_warning(&format!("failed to write to log ({:?}): {}", target, e));
+    }
+  }
+}
+
+fn severity(diff: &::index_diff::IndexDiff) -> u8 {
+  return match diff {
+    &::index_diff::IndexDiff::Deleted{..} => SEVERITY_ERR,
+    &::index_diff::IndexDiff::Modified{..} => SEVERITY_ERR,
+    _ => SEVERITY_NOTICE,
+  };
+}
+
+/**
+ * Send the records to the local syslog daemon in the RFC 5424 format. The
+ * structured data consists of the IANA-registered 'origin' element and an
+ * element with the fields of the record under the private SD-ID 'sd_id'
+ */
+fn send_syslog(
+    socket_path: &str,
+    sd_id: &str,
+    msgid: &str,
+    records: &Vec<LogRecord>) -> Result<(), ::Error> {
+  let socket = match UnixDatagram::unbound() {
+    Ok(s) => s,
+    Err(e) => return Err(e.to_string()),
+  };
+
+  let hostname = get_hostname();
+  let timestamp = time::now_utc().rfc3339().to_string();
+
+  let sd_element_origin = format!(
+      "[{} software=\"{}\" swVersion=\"{}\"]",
+      SYSLOG_SD_ID_ORIGIN,
+      APP_NAME,
+      encode_sd_value(::VERSION));
+
+  for record in records {
+    let mut sd_element = format!("{}[{}", sd_element_origin, sd_id);
+    for &(k, ref v) in &record.fields {
+      sd_element += &format!(" {}=\"{}\"", k, encode_sd_value(v));
+    }
+
+    sd_element += "]";
+
+    let msg = format!(
+        "<{}>1 {} {} {} {} {} {} {}",
+        SYSLOG_FACILITY_USER * 8 + record.severity,
+        timestamp,
+        hostname,
+        APP_NAME,
+        process::id(),
+        msgid,
+        sd_element,
+        record.message);
+
+    if let Err(e) = socket.send_to(msg.as_bytes(), socket_path) {
+      return Err(e.to_string());
+    }
+  }
+
+  return Ok(());
+}
+
+/**
+ * Send the records to the systemd journal using its native protocol
+ */
+fn send_journald(socket_path: &str, records: &Vec<LogRecord>) -> Result<(), ::Error> {
+  let socket = match UnixDatagram::unbound() {
+    Ok(s) => s,
+    Err(e) => return Err(e.to_string()),
+  };
+
+  for record in records {
+    let mut msg = Vec::<u8>::new();
+    append_journald_field(&mut msg, "MESSAGE", &record.message);
+    append_journald_field(&mut msg, "PRIORITY", &record.severity.to_string());
+    append_journald_field(&mut msg, "SYSLOG_IDENTIFIER", APP_NAME);
+
+    for &(k, ref v) in &record.fields {
+      append_journald_field(&mut msg, &format!("IC_{}", k.to_uppercase()), v);
+    }
+
+    if let Err(e) = socket.send_to(&msg, socket_path) {
+      return Err(e.to_string());
+    }
+  }
+
+  return Ok(());
+}
+
+fn append_journald_field(msg: &mut Vec<u8>, key: &str, value: &str) {
+  msg.extend(key.as_bytes());
+
+  if value.contains('\n') {
+    let len = value.len() as u64;
+    msg.push(b'\n');
+    msg.extend((0..8).map(|i| (len >> (i * 8)) as u8));
+    msg.extend(value.as_bytes());
+  } else {
+    msg.push(b'=');
+    msg.extend(value.as_bytes());
+  }
+
+  msg.push(b'\n');
+}
+
+fn encode_sd_value(value: &str) -> String {
+  return value
+      .replace("\\", "\\\\")
+      .replace("\"", "\\\"")
+      .replace("]", "\\]");
+}
+
+fn get_hostname() -> String {
+  let mut buf = [0u8; 256];
+  let res = unsafe {
+    libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len())
+  };
+
+  if res != 0 {
+    return "-".into();
+  }
+
+  let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+  return String::from_utf8_lossy(&buf[..len]).into_owned();
+}
@@ -24,9 +24,43 @@ mod op_verify;
 mod op_history;
 mod op_init;
 mod op_index;
+mod op_duplicates;
+mod op_du;
+mod op_ls_files;
+mod op_show;
+mod op_check;
+mod op_export;
+mod op_import;
+mod op_audit;
+mod op_compare;
+mod op_compare_index;
+mod op_recover;
+mod op_protect;
+mod op_repair;
+mod op_show_content;
+mod op_diff_content;
 mod index;
 mod index_diff;
 mod index_scan;
+mod index_ignore;
+mod path_trie;
+mod index_duplicates;
+mod index_usage;
+mod index_export;
+mod index_import;
+mod index_audit;
+mod index_compare;
+mod index_recover;
+mod parity;
+mod content_store;
+mod verify_state;
+mod text_diff;
+mod json;
+mod config;
+mod denylist;
+mod notify;
+mod logging;
+mod metrics;
 mod prompt;
 
 use std::env;
@@ -41,6 +75,20 @@ const VERSION : &'static str = env!("CARGO_PKG_VERSION");
 const DEFAULT_DATA_DIR : &'static str = ".";
 const DEFAULT_INDEX_DIR : &'static str = ".ic";
 const DEFAULT_CHECKSUM_FUNCTION : &'static str = "sha256";
+
+const EXIT_CLEAN : i32 = 0;
+const EXIT_CHANGED : i32 = 1;
+const EXIT_CORRUPTED : i32 = 2;
+const EXIT_UNREADABLE : i32 = 3;
+const EXIT_INDEX_ERROR : i32 = 4;
+const EXIT_ERROR : i32 = 5;
+const EXIT_ABORTED : i32 = 6;
+const EXIT_KNOWN_BAD : i32 = 7;
+const EXIT_USAGE : i32 = 64;
+const EXIT_NAGIOS_OK : i32 = 0;
+const EXIT_NAGIOS_WARNING : i32 = 1;
+const EXIT_NAGIOS_CRITICAL : i32 = 2;
+const EXIT_NAGIOS_UNKNOWN : i32 = 3;
 const USAGE : &'static str = "\
 usage: integritycheck <command> [options]
 Another file integrity monitoring tool.
@@ -56,24 +104,58 @@ global options:
   -v,--verbose           Enable verbose output,
   -h,--help              Print this help message and exit
 
+  Defaults for all options can be set in the file 'config' in the index
+  directory, in '~/.config/integritycheck/config' and in 'IC_<OPTION>'
+  environment variables. See the README for details.
+
 commands:
-  init      Create a new index file.
-  status    Compare the current state of the repository to the latest snapshot
-  ack       Acknowledge changes to files in the repository and create a new snapshot
-  log       Display a historical log of snapshots and changes to the repository
-  verify    Perform a full check of the repository's integrity
-  version   Print the version of this program and exit
-  help      Print the help message for one of the commands and exit
+  init           Create a new index file.
+  status         Compare the current state of the repository to the latest snapshot
+  ack            Acknowledge changes to files in the repository and create a new snapshot
+  log            Display a historical log of snapshots and changes to the repository
+  verify         Perform a full check of the repository's integrity
+  check          Check the repository and report the result in the Nagios plugin format
+  duplicates     Report files with identical contents
+  du             Display per-directory sizes from a snapshot without scanning
+  ls-files       List the files recorded in a snapshot
+  show           Display the header and a summary of a snapshot
+  export         Write a snapshot in the format of another checksum tool
+  import         Create a new snapshot from the manifest of another checksum tool
+  audit          Compare the repository against a set of known hashes
+  compare        Compare a copy of the repository against the latest snapshot
+  compare-index  Compare the latest snapshots of two index directories
+  recover        Locate good copies of deleted or corrupted files in a backup
+  protect        Compute recovery data for the files in a snapshot
+  repair         Reconstruct a corrupted file from its recovery data
+  show-content   Print the stored contents of a file as of a snapshot
+  diff-content   Display textual diffs of the files that changed between two snapshots
+  version        Print the version of this program and exit
+  help           Print the help message for one of the commands and exit
+
+exit codes:
+  0   Success; the repository matches the snapshot
+  1   Files were created, deleted, renamed or their metadata was modified
+  2   The contents of a file changed while its modification time did not;
+      this indicates corruption
+  3   One or more files could not be read
+  4   The index is missing, corrupted or invalid
+  5   Any other error
+  6   The operation was aborted by the user (e.g. a declined 'ack')
+  7   One or more files match a known-bad hash list
+  64  Invalid command line usage
+
+  The 'check' command uses the Nagios plugin exit codes instead.
 ";
 
 #[derive(Debug)]
 enum Command {
   PrintUsage{ topic: Option<Operation> },
   PrintVersion,
+  InvalidCommand{ name: String },
   Operation{ op: Operation, args: Vec<String> }
 }
 
-fn perform_op(op: Operation, args: &Vec<String>) -> Result<bool, Error> {
+fn perform_op(op: Operation, args: &Vec<String>) -> OperationResult {
   return match op {
     Operation::Acknowledge => op_acknowledge::perform(args),
     Operation::Status => op_status::perform(args),
@@ -81,10 +163,25 @@ fn perform_op(op: Operation, args: &Vec<String>) -> Result<bool, Error> {
     Operation::History => op_history::perform(args),
     Operation::Initialize => op_init::perform(args),
     Operation::Verify => op_verify::perform(args),
+    Operation::Duplicates => op_duplicates::perform(args),
+    Operation::DiskUsage => op_du::perform(args),
+    Operation::ListFiles => op_ls_files::perform(args),
+    Operation::Show => op_show::perform(args),
+    Operation::Check => op_check::perform(args),
+    Operation::Export => op_export::perform(args),
+    Operation::Import => op_import::perform(args),
+    Operation::Audit => op_audit::perform(args),
+    Operation::Compare => op_compare::perform(args),
+    Operation::CompareIndex => op_compare_index::perform(args),
+    Operation::Recover => op_recover::perform(args),
+    Operation::Protect => op_protect::perform(args),
+    Operation::Repair => op_repair::perform(args),
+    Operation::ShowContent => op_show_content::perform(args),
+    Operation::DiffContent => op_diff_content::perform(args),
   };
 }
 
-fn print_usage(op: Option<Operation>) -> Result<bool, Error> {
+fn print_usage(op: Option<Operation>) -> OperationResult {
   let usage_msg = match op {
     Some(Operation::Acknowledge) => op_acknowledge::USAGE,
     Some(Operation::Status) => op_status::USAGE,
@@ -92,21 +189,36 @@ fn print_usage(op: Option<Operation>) -> Result<bool, Error> {
     Some(Operation::History) => op_history::USAGE,
     Some(Operation::Initialize) => op_init::USAGE,
     Some(Operation::Verify) => op_verify::USAGE,
+    Some(Operation::Duplicates) => op_duplicates::USAGE,
+    Some(Operation::DiskUsage) => op_du::USAGE,
+    Some(Operation::ListFiles) => op_ls_files::USAGE,
+    Some(Operation::Show) => op_show::USAGE,
+    Some(Operation::Check) => op_check::USAGE,
+    Some(Operation::Export) => op_export::USAGE,
+    Some(Operation::Import) => op_import::USAGE,
+    Some(Operation::Audit) => op_audit::USAGE,
+    Some(Operation::Compare) => op_compare::USAGE,
+    Some(Operation::CompareIndex) => op_compare_index::USAGE,
+    Some(Operation::Recover) => op_recover::USAGE,
+    Some(Operation::Protect) => op_protect::USAGE,
+    Some(Operation::Repair) => op_repair::USAGE,
+    Some(Operation::ShowContent) => op_show_content::USAGE,
+    Some(Operation::DiffContent) => op_diff_content::USAGE,
     None => USAGE,
   };
 
   match std::io::stdout().write(usage_msg.as_bytes()) {
-    Err(e) => Err(e.to_string()),
-    Ok(_) => Ok(true)
+    Err(e) => Err(e.to_string().into()),
+    Ok(_) => Ok(Outcome::Clean)
   }
 }
 
-fn print_version() -> Result<bool, Error> {
+fn print_version() -> OperationResult {
   println!("integritycheck v{}", VERSION);
   println!("Copyright (c) 2018 Paul Asmuth");
   println!("Licensed under the Apache License, Version 2.0");
   println!("https://github.com/asmuth/integritycheck");
-  return Ok(true);
+  return Ok(Outcome::Clean);
 }
 
 fn main() {
@@ -132,7 +244,7 @@ fn main() {
             Command::Operation{ op: op, args: args[2..].to_vec() }
           },
         _ =>
-          Command::PrintUsage{ topic: None },
+          Command::InvalidCommand{ name: cmd.to_string() },
       },
     _ =>
       Command::PrintUsage{ topic: None },
@@ -141,15 +253,34 @@ fn main() {
   let result = match command {
     Command::PrintUsage{topic} => print_usage(topic),
     Command::PrintVersion => print_version(),
+    Command::InvalidCommand{name} =>
+      print_usage(None).and(Err(usage_error(format!("invalid command: {}", name)))),
     Command::Operation{op, args} => perform_op(op, &args),
   };
 
-  match result {
-    Ok(true) => return,
-    Ok(false) => std::process::exit(1),
+  let exit_code = match result {
+    Ok(Outcome::Clean) => EXIT_CLEAN,
+    Ok(Outcome::Changed) => EXIT_CHANGED,
+    Ok(Outcome::Corrupted) => EXIT_CORRUPTED,
+    Ok(Outcome::KnownBad) => EXIT_KNOWN_BAD,
+    Ok(Outcome::Aborted) => EXIT_ABORTED,
+    Ok(Outcome::Nagios(state)) => match state {
+      NagiosState::Ok => EXIT_NAGIOS_OK,
+      NagiosState::Warning => EXIT_NAGIOS_WARNING,
+      NagiosState::Critical => EXIT_NAGIOS_CRITICAL,
+      NagiosState::Unknown => EXIT_NAGIOS_UNKNOWN,
+    },
     Err(e) => {
-      writeln!(&mut std::io::stderr(), "{}", format!("ERROR: {}", e).red()).expect("ERROR");
-      std::process::exit(1);
+      writeln!(&mut std::io::stderr(), "{}", format!("ERROR: {}", e.message).red()).expect("ERROR");
+
+      match e.kind {
+        ErrorKind::Usage => EXIT_USAGE,
+        ErrorKind::Index => EXIT_INDEX_ERROR,
+        ErrorKind::Unreadable => EXIT_UNREADABLE,
+        ErrorKind::Other => EXIT_ERROR,
+      }
     }
-  }
+  };
+
+  std::process::exit(exit_code);
 }
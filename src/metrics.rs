@@ -0,0 +1,173 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::fs;
+use std::io::{Read,Write};
+use std::path::Path;
+
+const LAST_SUCCESS_METRIC : &'static str = "integritycheck_last_success_timestamp_seconds";
+
+pub struct ScanStats {
+  pub duration_us: i64,
+  pub bytes_scanned: u64,
+  pub oldest_verification_us: Option<i64>,
+}
+
+/**
+ * Write the result of a status or verify run to a file in the format of the
+ * Prometheus node_exporter textfile collector. The file is replaced atomically.
+ */
+pub fn write_prometheus_file(
+    path: &Path,
+    command: &str,
+    data_path: &str,
+    snap_ref: &::IndexReference,
+    snap: &::IndexSnapshot,
+    diff: &::index_diff::IndexDiffList,
+    known_bad: &Vec<::denylist::DenylistMatch>,
+    stats: &ScanStats,
+    timestamp_us: i64) -> Result<(), ::Error> {
+  let labels = format!(
+      "command={},repository={}",
+      encode_label(command),
+      encode_label(&::prompt::format_repository_path(data_path)));
+
+  let last_success = if diff.len() == 0 && known_bad.len() == 0 {
+    Some(timestamp_us as f64 / 1_000_000.0)
+  } else {
+    read_last_success(path, &labels)
+  };
+
+  let mut data = String::new();
+
+  data += "# HELP integritycheck_changes Number of changes found in the repository, by kind\n";
+  data += "# TYPE integritycheck_changes gauge\n";
+  for kind in ::index_diff::DIFF_KINDS.iter() {
+    data += &format!(
+        "integritycheck_changes{{{},kind={}}} {}\n",
+        labels,
+        encode_label(kind),
+        ::index_diff::count_kind(diff, kind));
+  }
+
+  data += &format_gauge(
+      "integritycheck_known_bad_files",
+      "Number of files matching a known-bad hash list",
+      &labels,
+      known_bad.len() as f64);
+
+  data += &format_gauge(
+      "integritycheck_snapshot_files",
+      "Number of files in the latest snapshot",
+      &labels,
+      snap.total_file_count() as f64);
+
+  data += &format_gauge(
+      "integritycheck_snapshot_bytes",
+      "Total size of all files in the latest snapshot",
+      &labels,
+      snap.total_size_bytes() as f64);
+
+  data += &format_gauge(
+      "integritycheck_snapshot_timestamp_seconds",
+      "Time at which the latest snapshot was created",
+      &labels,
+      snap_ref.timestamp_us as f64 / 1_000_000.0);
+
+  data += &format_gauge(
+      "integritycheck_scan_duration_seconds",
+      "Time spent scanning the repository",
+      &labels,
+      stats.duration_us as f64 / 1_000_000.0);
+
+  data += &format_gauge(
+      "integritycheck_scan_bytes",
+      "Number of bytes read while computing checksums",
+      &labels,
+      stats.bytes_scanned as f64);
+
+  data += &format_gauge(
+      "integritycheck_scan_throughput_bytes_per_second",
+      "Average checksum throughput of the scan",
+      &labels,
+      if stats.duration_us > 0 {
+        stats.bytes_scanned as f64 / (stats.duration_us as f64 / 1_000_000.0)
+      } else {
+        0.0
+      });
+
+  data += &format_gauge(
+      "integritycheck_last_run_timestamp_seconds",
+      "Time at which the last run completed",
+      &labels,
+      timestamp_us as f64 / 1_000_000.0);
+
+  if let Some(oldest_verification_us) = stats.oldest_verification_us {
+    data += &format_gauge(
+        "integritycheck_oldest_verification_age_seconds",
+        "Time since the least recently verified file was last verified",
+        &labels,
+        (timestamp_us - oldest_verification_us) as f64 / 1_000_000.0);
+  }
+
+  if let Some(last_success) = last_success {
+    data += &format_gauge(
+        LAST_SUCCESS_METRIC,
+        "Time at which the last run found no changes and no known-bad files",
+        &labels,
+        last_success);
+  }
+
+  let path_tmp = path.with_extension("prom.tmp");
+  let result =
+      fs::File::create(&path_tmp)
+      .and_then(|mut f| f.write_all(data.as_bytes()).and_then(|_| f.sync_all()))
+      .and_then(|_| fs::rename(&path_tmp, path));
+
+  return match result {
+    Ok(_) => Ok(()),
+    Err(e) => Err(format!("error while writing metrics file: {}", e)),
+  };
+}
+
+fn format_gauge(name: &str, help: &str, labels: &str, value: f64) -> String {
+  return format!(
+      "# HELP {} {}\n# TYPE {} gauge\n{}{{{}}} {}\n",
+      name,
+      help,
+      name,
+      name,
+      labels,
+      value);
+}
+
+fn encode_label(value: &str) -> String {
+  let value = value
+      .replace("\\", "\\\\")
+      .replace("\"", "\\\"")
+      .replace("\n", "\\n");
+
+  return format!("\"{}\"", value);
+}
+
+/**
+ * A run that found changes does not reset the time of the last clean run, so
+ * carry over the previous value from the existing metrics file
+ */
+fn read_last_success(path: &Path, labels: &str) -> Option<f64> {
+  let mut data = String::new();
+  if fs::File::open(path).and_then(|mut f| f.read_to_string(&mut data)).is_err() {
+    return None;
+  }
+
+  let prefix = format!("{}{{{}}} ", LAST_SUCCESS_METRIC, labels);
+  return data
+      .lines()
+      .find(|l| l.starts_with(&prefix))
+      .and_then(|l| l[prefix.len()..].parse::<f64>().ok());
+}
@@ -0,0 +1,225 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::io::{Read,Write};
+use std::net::TcpStream;
+use std::process::{Command,Stdio};
+use std::time::Duration;
+use time;
+
+const DEFAULT_SENDMAIL_COMMAND : &'static str = "/usr/sbin/sendmail";
+const WEBHOOK_TIMEOUT_SECS : u64 = 30;
+
+/**
+ * Send notifications about the changes found by a status or verify run. The
+ * notifiers are configured in the repository's config file:
+ *
+ *   notify_on = modified,deleted
+ *   notify_sendmail = admin@example.com
+ *   notify_sendmail_command = /usr/sbin/sendmail
+ *   notify_webhook = http://localhost:8080/integritycheck
+ *   notify_command = /usr/local/bin/handle-report
+ *
+ * Notifications are sent if at least one change of the kinds listed in
+ * 'notify_on' (default: all kinds) or any known-bad file was found. Failures to
+ * deliver a notification are reported as warnings.
+ */
+pub fn notify(
+    config: &::config::Config,
+    command: &str,
+    data_path: &str,
+    snap_ref: &::IndexReference,
+    snap: &::IndexSnapshot,
+    diff: &::index_diff::IndexDiffList,
+    known_bad: &Vec<::denylist::DenylistMatch>) -> Result<(), ::Error> {
+  let kinds = match config.get("notify_on") {
+    Some(v) => v.split(',').map(|k| k.trim().to_owned()).collect::<Vec<String>>(),
+    None => ::index_diff::DIFF_KINDS.iter().map(|k| k.to_string()).collect(),
+  };
+
+  for kind in &kinds {
+    if !::index_diff::DIFF_KINDS.contains(&kind.as_str()) {
+      return Err(format!("invalid value for notify_on: {}", kind));
+    }
+  }
+
+  if known_bad.len() == 0 && !diff.iter().any(|d| kinds.iter().any(|k| k == d.kind_name())) {
+    return Ok(());
+  }
+
+  let report_json = format_report_json(command, data_path, snap_ref, snap, diff, known_bad);
+  let report_text = format_report_text(data_path, snap_ref, diff, known_bad);
+
+  let mut subject = format!("{} changes", diff.len());
+  if known_bad.len() > 0 {
+    subject += &format!(", {} known-bad files", known_bad.len());
+  }
+
+  let sendmail_command = config
+      .get("notify_sendmail_command")
+      .unwrap_or(DEFAULT_SENDMAIL_COMMAND.into());
+
+  for addr in config.get_all("notify_sendmail") {
+    ::prompt::print_debug(&format!("Sending notification mail to {}", addr));
+    let mail = format!(
+        "To: {}\nSubject: [integritycheck] {}: {}\n\n{}",
+        addr,
+        ::prompt::format_repository_path(data_path),
+        subject,
+        report_text);
+
+    let result = run_command(
+        Command::new(&sendmail_command).arg("-t").arg("-oi"),
+        &mail);
+
+    if let Err(e) = result {
+      ::prompt::print_warning(&format!("failed to send mail to {}: {}", addr, e));
+    }
+  }
+
+  for url in config.get_all("notify_webhook") {
+    ::prompt::print_debug(&format!("Sending notification to webhook {}", url));
+    if let Err(e) = post_webhook(&url, &report_json) {
+      ::prompt::print_warning(&format!("failed to call webhook {}: {}", url, e));
+    }
+  }
+
+  for cmd in config.get_all("notify_command") {
+    ::prompt::print_debug(&format!("Running notification command {:?}", cmd));
+    if let Err(e) = run_command(Command::new("sh").arg("-c").arg(&cmd), &report_json) {
+      ::prompt::print_warning(&format!("notification command {:?} failed: {}", cmd, e));
+    }
+  }
+
+  return Ok(());
+}
+
+fn format_report_json(
+    command: &str,
+    data_path: &str,
+    snap_ref: &::IndexReference,
+    snap: &::IndexSnapshot,
+    diff: &::index_diff::IndexDiffList,
+    known_bad: &Vec<::denylist::DenylistMatch>) -> String {
+  let diffs : Vec<String> = ::prompt::sort_diff(diff)
+      .iter()
+      .map(|d| ::json::encode_object(&::json::diff_fields(d)))
+      .collect();
+
+  let mut report = ::json::status_fields(command, data_path, snap_ref, snap, diff);
+  report.push(("diffs", ::json::encode_list(&diffs)));
+  report.push(("known_bad", ::json::encode_known_bad(known_bad)));
+  return ::json::encode_object(&report) + "\n";
+}
+
+fn format_report_text(
+    data_path: &str,
+    snap_ref: &::IndexReference,
+    diff: &::index_diff::IndexDiffList,
+    known_bad: &Vec<::denylist::DenylistMatch>) -> String {
+  let snap_time = time::at(time::Timespec::new(snap_ref.timestamp_us / 1_000_000, 0));
+
+  let mut report = String::new();
+  report += &format!("Repository: {}\n", ::prompt::format_repository_path(data_path));
+  report += &format!("Last Snapshot: {}\n", snap_time.rfc822z());
+  report += "Status: DIRTY\n\n";
+
+  for d in ::prompt::sort_diff(diff) {
+    report += &::prompt::format_diff(&d);
+    report += "\n";
+  }
+
+  for m in known_bad {
+    report += &format!("    known-bad {:?} (listed in {})\n", m.file, m.list);
+  }
+
+  return report;
+}
+
+fn run_command(command: &mut Command, input: &str) -> Result<(), ::Error> {
+  let mut child = match command.stdin(Stdio::piped()).spawn() {
+    Ok(c) => c,
+    Err(e) => return Err(e.to_string()),
+  };
+
+  if let Some(mut stdin) = child.stdin.take() {
+    if let Err(e) = stdin.write_all(input.as_bytes()) {
+      return Err(e.to_string());
+    }
+  }
+
+  return match child.wait() {
+    Ok(status) if status.success() => Ok(()),
+    Ok(status) => Err(format!("exited with {}", status)),
+    Err(e) => Err(e.to_string()),
+  };
+}
+
+fn post_webhook(url: &str, body: &str) -> Result<(), ::Error> {
+  let url = match url.find("://") {
+    Some(i) if &url[..i] == "http" => &url[i + 3..],
+    _ => return Err(format!("only http:// URLs are supported")),
+  };
+
+  let (host, path) = match url.find('/') {
+    Some(i) => (&url[..i], &url[i..]),
+    None => (url, "/"),
+  };
+
+  let addr = if host.contains(':') {
+    host.to_owned()
+  } else {
+    format!("{}:80", host)
+  };
+
+  let mut stream = match TcpStream::connect(&addr) {
+    Ok(s) => s,
+    Err(e) => return Err(e.to_string()),
+  };
+
+  let timeout = Some(Duration::from_secs(WEBHOOK_TIMEOUT_SECS));
+  if let Err(e) = stream.set_read_timeout(timeout).and(stream.set_write_timeout(timeout)) {
+    return Err(e.to_string());
+  }
+
+  let request = format!(
+      "POST {} HTTP/1.1\r\n\
+       Host: {}\r\n\
+       User-Agent: integritycheck/{}\r\n\
+       Content-Type: application/json\r\n\
+       Content-Length: {}\r\n\
+       Connection: close\r\n\
+       \r\n\
+       {}",
+      path,
+      host,
+      ::VERSION,
+      body.len(),
+      body);
+
+  if let Err(e) = stream.write_all(request.as_bytes()) {
+    return Err(e.to_string());
+  }
+
+  let mut response = String::new();
+  if let Err(e) = stream.read_to_string(&mut response) {
+    return Err(e.to_string());
+  }
+
+  let status = response
+      .lines()
+      .next()
+      .and_then(|l| l.split(' ').nth(1))
+      .and_then(|s| s.parse::<u32>().ok());
+
+  return match status {
+    Some(s) if s >= 200 && s < 300 => Ok(()),
+    Some(s) => Err(format!("server returned HTTP status {}", s)),
+    None => Err(format!("invalid HTTP response")),
+  };
+}
@@ -6,6 +6,8 @@
  * licensed under the Apache License, Version 2.0 (the "License"); you may not
  * use this file except in compliance with the License.
  */
+use std::path::{Component,Path,PathBuf};
+
 #[derive(Debug)]
 pub enum Operation {
   Acknowledge,
@@ -13,9 +15,78 @@ pub enum Operation {
   Verify,
   Index,
   History,
-  Initialize
+  Initialize,
+  Duplicates,
+  DiskUsage,
+  ListFiles,
+  Show,
+  Check,
+  Export,
+  Import,
+  Audit,
+  Compare,
+  CompareIndex,
+  Recover,
+  Protect,
+  Repair,
+  ShowContent,
+  DiffContent
+}
+
+/**
+ * The result of a successful operation. The outcomes are ordered by severity
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+  Clean,
+  Changed,
+  Corrupted,
+  KnownBad,
+  Aborted,
+  Nagios(NagiosState),
+}
+
+/**
+ * The states of a Nagios plugin. The 'check' command reports one of these
+ * instead of the usual outcomes
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NagiosState {
+  Ok,
+  Warning,
+  Critical,
+  Unknown,
 }
 
+impl NagiosState {
+
+  pub fn name(self: &Self) -> &'static str {
+    return match *self {
+      NagiosState::Ok => "OK",
+      NagiosState::Warning => "WARNING",
+      NagiosState::Critical => "CRITICAL",
+      NagiosState::Unknown => "UNKNOWN",
+    };
+  }
+
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+  Usage,
+  Index,
+  Unreadable,
+  Other,
+}
+
+#[derive(Clone, Debug)]
+pub struct OperationError {
+  pub kind: ErrorKind,
+  pub message: ::Error,
+}
+
+pub type OperationResult = Result<Outcome, OperationError>;
+
 pub trait OperationHelp {
   fn usage(self: &Self) -> String;
 }
@@ -30,8 +101,93 @@ impl Operation {
       "index" => Some(Operation::Index),
       "init" => Some(Operation::Initialize),
       "log" => Some(Operation::History),
+      "duplicates" => Some(Operation::Duplicates),
+      "du" => Some(Operation::DiskUsage),
+      "ls-files" => Some(Operation::ListFiles),
+      "show" => Some(Operation::Show),
+      "check" => Some(Operation::Check),
+      "export" => Some(Operation::Export),
+      "import" => Some(Operation::Import),
+      "audit" => Some(Operation::Audit),
+      "compare" => Some(Operation::Compare),
+      "compare-index" => Some(Operation::CompareIndex),
+      "recover" => Some(Operation::Recover),
+      "protect" => Some(Operation::Protect),
+      "repair" => Some(Operation::Repair),
+      "show-content" => Some(Operation::ShowContent),
+      "diff-content" => Some(Operation::DiffContent),
       _ => None,
     };
   }
 
 }
+
+impl From<::Error> for OperationError {
+
+  fn from(message: ::Error) -> OperationError {
+    return OperationError {
+      kind: ErrorKind::Other,
+      message: message,
+    };
+  }
+
+}
+
+pub fn usage_error(message: ::Error) -> OperationError {
+  return OperationError { kind: ErrorKind::Usage, message: message };
+}
+
+pub fn index_error(message: ::Error) -> OperationError {
+  return OperationError { kind: ErrorKind::Index, message: message };
+}
+
+pub fn unreadable_error(message: ::Error) -> OperationError {
+  return OperationError { kind: ErrorKind::Unreadable, message: message };
+}
+
+/**
+ * Classify a list of changes. Files whose contents changed while their
+ * modification time stayed the same are reported as 'modified' by the diff and
+ * indicate corruption; all other kinds of changes are benign.
+ */
+pub fn outcome_from_diff(diff: &::index_diff::IndexDiffList) -> Outcome {
+  if ::index_diff::count_kind(diff, "modified") > 0 {
+    return Outcome::Corrupted;
+  }
+
+  if diff.len() > 0 {
+    return Outcome::Changed;
+  }
+
+  return Outcome::Clean;
+}
+
+/**
+ * Derive the outcome of a status or verify run. Files matching a known-bad hash
+ * list take precedence over all changes
+ */
+pub fn outcome_from_status(
+    diff: &::index_diff::IndexDiffList,
+    known_bad: &Vec<::denylist::DenylistMatch>) -> Outcome {
+  if known_bad.len() > 0 {
+    return Outcome::KnownBad;
+  }
+
+  return outcome_from_diff(diff);
+}
+
+/**
+ * Parse a path that is relative to the repository root, e.g. 'photos/2018/'
+ */
+pub fn parse_repository_path(path: &str) -> Result<PathBuf, ::Error> {
+  let path : PathBuf = Path::new(path)
+      .components()
+      .filter(|c| *c != Component::CurDir)
+      .collect();
+
+  if path.has_root() {
+    return Err(format!("path must be relative to the repository root: {:?}", path));
+  }
+
+  return Ok(path);
+}
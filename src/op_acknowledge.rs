@@ -12,12 +12,33 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use getopts::Options;
 
 pub const USAGE : &'static str = "\
-usage: integritycheck ack [options] <path>
+usage: integritycheck ack [options] [<path>...]
 Acknowledge changes to files in the repository and create a new snapshot
 
+If paths are given, only changes to files below these paths are acknowledged;
+all other files are carried over from the previous snapshot. The paths are
+relative to the repository root.
+
 options:
   -m,--message=MSG       Set a message to be stored along with the snapshot
   -y,--noconfirm         Don't prompt to confirm changes
+  --allow_known_bad      Create the snapshot even if files match one of the
+                         configured known-bad hash lists
+  --store_content=SIZE   Store a copy of every file that is no larger than SIZE
+                         (e.g. '64K') in the index directory. default: off
+  --format=FORMAT        Set the output format. Possible values:
+                         'text' (default), 'json', 'jsonl'
+  --log=TARGETS          Send a summary of this run and the changes found to the
+                         system log. Comma-separated list of 'syslog' and
+                         'journald', each optionally followed by ':SOCKET'
+  --syslog_enterprise_number=PEN
+                         Set the IANA private enterprise number that identifies
+                         the structured data of syslog messages. Required for
+                         the 'syslog' log target
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
   -d,--data_dir=PATH     Set the path of the repository/data directory
                          default: '.'
   -x,--index_dir=PATH    Set the path of the index directory. Note that this
@@ -33,10 +54,17 @@ options:
   -h,--help              Print this help message and exit
 ";
 
-pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
   let mut flag_cfg = Options::new();
   flag_cfg.optopt("m", "message", "message", "MSG");
   flag_cfg.optflag("y", "noconfirm", "noconfirm");
+  flag_cfg.optflag("", "allow_known_bad", "allow_known_bad");
+  flag_cfg.optopt("", "store_content", "store_content", "SIZE");
+  flag_cfg.optopt("", "format", "format", "FORMAT");
+  flag_cfg.optopt("", "log", "log", "TARGETS");
+  flag_cfg.optopt("", "syslog_enterprise_number", "syslog_enterprise_number", "PEN");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
   flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
   flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
   flag_cfg.optopt("", "progress", "progress", "ONOFF");
@@ -44,9 +72,9 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
   flag_cfg.optopt("", "set_time", "set_time", "TIMESTAMP");
   flag_cfg.optflag("v", "verbose", "verbose");
 
-  let flags = match flag_cfg.parse(args) {
+  let flags = match ::config::parse_flags("ack", &flag_cfg, args) {
     Ok(f) => f,
-    Err(e) => return Err(e.to_string()),
+    Err(e) => return Err(::op::usage_error(e)),
   };
 
   ::prompt::set_debug(flags.opt_present("verbose"));
@@ -55,10 +83,24 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
 
   let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
   let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+  let format = ::prompt::parse_output_format(flags.opt_str("format"))
+      .map_err(::op::usage_error)?;
+  let log_targets = ::logging::parse_targets(
+      &flags.opt_str("log").unwrap_or(String::new()),
+      flags.opt_str("syslog_enterprise_number")).map_err(::op::usage_error)?;
+  let store_content = match flags.opt_str("store_content") {
+    Some(ref v) if v == "off" => None,
+    Some(v) => Some(::prompt::parse_bytecount(&v).map_err(::op::usage_error)?),
+    None => None,
+  };
+
+  if format != ::prompt::OutputFormat::Text && !flags.opt_present("noconfirm") {
+    return Err(::op::usage_error("machine-readable output requires --noconfirm".into()));
+  }
 
   let data_path_abs = match fs::canonicalize(&data_path) {
     Ok(p) => p,
-    Err(e) => return Err(e.to_string()),
+    Err(e) => return Err(::op::unreadable_error(e.to_string())),
   };
 
   let time = match flags.opt_str("set_time").and_then(|x| x.parse::<i64>().ok()) {
@@ -67,40 +109,39 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
       let now = SystemTime::now();
       match now.duration_since(UNIX_EPOCH) {
         Ok(v) => v.as_secs() as i64 * 1_000_000 + v.subsec_nanos() as i64 / 1_000,
-        Err(e) => return Err(format!("internal error: {}", e)),
+        Err(e) => return Err(format!("internal error: {}", e).into()),
       }
     }
   };
 
-  //let mut pathspecs = Vec::<PathBuf>::new();
-  //for pathspec in &flags.free {
-  //  let pathspec = match fs::canonicalize(pathspec) {
-  //    Ok(e) => e,
-  //    Err(e) => return Err(e.to_string()),
-  //  };
-
-  //  if !pathspec.starts_with(&data_path_abs) {
-  //    return Err(format!("path is outside of repository: {:?}", pathspec));
-  //  }
-
-  //  match pathspec.strip_prefix(&data_path_abs) {
-  //    Ok(v) => pathspecs.push(PathBuf::from(v)),
-  //    Err(e) => return Err(e.to_string()),
-  //  };
-  //}
-
-  //if pathspecs.len() == 0 {
-  //  return Err("need a path (e.g. 'integritycheck ack .')".into());
-  //}
+  let mut pathspecs = Vec::<PathBuf>::new();
+  for pathspec in &flags.free {
+    pathspecs.push(::op::parse_repository_path(pathspec).map_err(::op::usage_error)?);
+  }
 
   ::prompt::print_progress_step(1, 4, "Loading index");
   let mut index = ::IndexDirectory::open(
       &Path::new(&data_path),
-      &Path::new(&index_path))?;
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+  let config = ::config::Config::load(&index.config_path())?;
 
-  let snapshot_old = match index.latest() {
-    Some(idx) => index.load(&idx)?,
-    None => return Err(format!("no index snapshot found")),
+  let snapshot_old_ref = match index.latest() {
+    Some(idx) => idx,
+    None => return Err(::op::index_error(format!("no index snapshot found"))),
+  };
+
+  let snapshot_old = index.load(&snapshot_old_ref).map_err(::op::index_error)?;
+  let denylist = ::denylist::Denylist::load(
+      &config,
+      index.path(),
+      &snapshot_old.checksum_function)?;
+
+  let scan_opts = ::index_scan::ScanOptions {
+    exclude_paths: vec!(PathBuf::from(&index_path)).into(),
+    exclusive_paths: if pathspecs.len() > 0 { Some(pathspecs.into()) } else { None },
+    ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+        &flags.opt_strs("exclude"),
+        &flags.opt_strs("include")).map_err(::op::usage_error)?,
   };
 
   ::prompt::print_progress_step(2, 4, "Scanning file metadata");
@@ -108,10 +149,15 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
   let mut snapshot_new = ::index_scan::scan_metadata(
       &Path::new(&data_path),
       ::IndexSnapshot::new(snapshot_old.checksum_function.to_owned()),
-      &::index_scan::ScanOptions {
-        exclude_paths: vec!(PathBuf::from(&index_path)),
-        exclusive_paths: None,
-      })?;
+      &scan_opts).map_err(::op::unreadable_error)?;
+
+  if let Some(ref exclusive_paths) = scan_opts.exclusive_paths {
+    for (file_path, file_info) in snapshot_old.files.iter() {
+      if !exclusive_paths.contains_prefix_of(&Path::new(file_path)) {
+        snapshot_new.update(file_path, file_info);
+      }
+    }
+  }
 
   ::prompt::print_progress_step(3, 4, "Computing file checksums for changed files");
 
@@ -122,33 +168,81 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
   snapshot_new = ::index_scan::scan_checksums(
       &Path::new(&data_path),
       snapshot_new.to_owned(),
-      &::index_scan::ScanOptions {
-        exclude_paths: vec!(PathBuf::from(&index_path)),
-        exclusive_paths: None
-      })?;
+      &scan_opts).map_err(::op::unreadable_error)?;
 
   ::prompt::print_progress_step(4, 4, "Computing diff");
   let diffs = ::index_diff::diff(&snapshot_old, &snapshot_new);
+  let known_bad = denylist.check(&snapshot_new);
 
   ::prompt::print_progress_complete();
 
+  let mut summary = ::json::status_fields(
+      "ack",
+      &data_path,
+      &snapshot_old_ref,
+      &snapshot_old,
+      &diffs);
+
+  if !denylist.is_empty() {
+    summary.push(("known_bad", ::json::encode_known_bad(&known_bad)));
+  }
+
+  if format == ::prompt::OutputFormat::Text {
+    ::prompt::print_known_bad(&known_bad);
+  }
+
+  if known_bad.len() > 0 && !flags.opt_present("allow_known_bad") {
+    ::logging::log_run(&log_targets, "ack", &data_path, &snapshot_old_ref, &diffs, &known_bad);
+    if format == ::prompt::OutputFormat::Text {
+      ::prompt::print_warning(
+          "not creating a snapshot with known-bad files; use --allow_known_bad to override");
+    } else {
+      summary.push(("created_snapshot", "null".into()));
+      ::prompt::print_status_json(&format, summary, &diffs);
+    }
+
+    return Ok(::op::Outcome::KnownBad);
+  }
+
   if diffs.len() == 0 {
-    ::prompt::print_success(&format!("Nothing to commit"));
-    return Ok(true);
+    ::logging::log_run(&log_targets, "ack", &data_path, &snapshot_old_ref, &diffs, &known_bad);
+    if format == ::prompt::OutputFormat::Text {
+      ::prompt::print_success(&format!("Nothing to commit"));
+    } else {
+      summary.push(("created_snapshot", "null".into()));
+      ::prompt::print_status_json(&format, summary, &diffs);
+    }
+
+    return Ok(::op::Outcome::Clean);
   }
 
   if !flags.opt_present("noconfirm") {
     if !::prompt::confirm_diffs(&diffs) {
-      return Ok(false);
+      return Ok(::op::Outcome::Aborted);
     }
-  } else {
+  } else if format == ::prompt::OutputFormat::Text {
     ::prompt::print_confirmed_diffs(&diffs);
   }
 
   snapshot_new.message = flags.opt_str("message");
 
-  let updated_ref = index.append(&snapshot_new, time)?;
-  ::prompt::print_success(&format!("Created snapshot {:?}", updated_ref.checksum));
+  if let Some(max_size) = store_content {
+    ::content_store::store(
+        &index,
+        &Path::new(&data_path),
+        &snapshot_new,
+        max_size).map_err(::op::index_error)?;
+  }
+
+  let updated_ref = index.append(&snapshot_new, time).map_err(::op::index_error)?;
+  ::logging::log_run(&log_targets, "ack", &data_path, &snapshot_old_ref, &diffs, &known_bad);
+
+  if format == ::prompt::OutputFormat::Text {
+    ::prompt::print_success(&format!("Created snapshot {:?}", updated_ref.checksum));
+  } else {
+    summary.push(("created_snapshot", ::json::encode_snapshot_ref(&updated_ref)));
+    ::prompt::print_status_json(&format, summary, &diffs);
+  }
 
-  return Ok(true);
+  return Ok(::op::Outcome::Clean);
 }
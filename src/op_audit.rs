@@ -0,0 +1,149 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::fs;
+use std::path::{Path,PathBuf};
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck audit [options] --known=<manifest>
+Compare the repository against a set of known hashes regardless of path
+
+Every file is classified as matched (same checksum as the known file at the
+same path), moved (the checksum is only known at a different path) or new (the
+checksum is not known). Known files whose checksum was not found anywhere are
+reported as missing. By default, the checksums of all files in the repository
+are computed; use --snapshot to audit a snapshot instead.
+
+options:
+  --known=FILE           Read the known hashes from FILE. Use '-' for stdin
+  --known_format=FORMAT  Set the format of the known hashes. Possible values:
+                         'sha256sum' (default), 'md5sum', 'hashdeep', 'sfv',
+                         'mtree'
+  --snapshot=ID          Audit the specified snapshot instead of the files in
+                         the repository
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --progress=[on/off]    Turn progress reporting on stderr on or off
+                         default: off
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "known", "known", "FILE");
+  flag_cfg.optopt("", "known_format", "known_format", "FORMAT");
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "progress", "progress", "ONOFF");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("audit", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(flags.opt_str("progress") == Some("on".to_owned()));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+  let known_format = ::index_import::parse_import_format(
+      &flags.opt_str("known_format").unwrap_or("sha256sum".into()))
+      .map_err(::op::usage_error)?;
+
+  let known_path = match flags.opt_str("known") {
+    Some(p) => p,
+    None => return Err(::op::usage_error("need a set of known hashes (--known=FILE)".into())),
+  };
+
+  let data_path_abs = match fs::canonicalize(&data_path) {
+    Ok(p) => p,
+    Err(e) => return Err(::op::unreadable_error(e.to_string())),
+  };
+
+  ::prompt::print_progress_step(1, 3, "Reading known hashes");
+  let known = ::index_import::read_manifest(&known_path, &known_format)
+      .map_err(::op::usage_error)?;
+
+  let known_entries = known
+      .entries
+      .iter()
+      .map(|&(ref p, ref c)| (::index_import::normalize_path(p, &data_path_abs), c.to_owned()))
+      .collect();
+
+  let snapshot = match flags.opt_str("snapshot") {
+    Some(id) => {
+      ::prompt::print_progress_step(2, 3, "Loading index");
+      let index = ::IndexDirectory::open(
+          &Path::new(&data_path),
+          &Path::new(&index_path)).map_err(::op::index_error)?;
+
+      let snapshot_ref = index.find(&id).map_err(::op::usage_error)?;
+      let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+
+      let snapshot_checksum_fn = ::checksum::checksum_function_to_str(&snapshot.checksum_function);
+      let known_checksum_fn = ::checksum::checksum_function_to_str(&known.checksum_function);
+      if snapshot_checksum_fn != known_checksum_fn {
+        return Err(::op::usage_error(format!(
+            "the snapshot uses {} checksums, but the known hashes are {}",
+            snapshot_checksum_fn,
+            known_checksum_fn)));
+      }
+
+      snapshot
+    },
+    None => {
+      ::prompt::print_progress_step(2, 3, "Computing file checksums");
+      let scan_opts = ::index_scan::ScanOptions {
+        exclude_paths: vec!(PathBuf::from(&index_path)).into(),
+        exclusive_paths: None,
+        ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+            &flags.opt_strs("exclude"),
+            &flags.opt_strs("include")).map_err(::op::usage_error)?,
+      };
+
+      let snapshot = ::index_scan::scan_metadata(
+          &Path::new(&data_path),
+          ::IndexSnapshot::new(known.checksum_function.to_owned()),
+          &scan_opts).map_err(::op::unreadable_error)?;
+
+      ::index_scan::scan_checksums(
+          &Path::new(&data_path),
+          snapshot,
+          &scan_opts).map_err(::op::unreadable_error)?
+    },
+  };
+
+  ::prompt::print_progress_step(3, 3, "Comparing checksums");
+  let result = ::index_audit::audit(&snapshot, &known_entries);
+
+  ::prompt::print_progress_complete();
+  ::prompt::print_audit_result(&result);
+
+  if result.is_clean() {
+    return Ok(::op::Outcome::Clean);
+  } else {
+    return Ok(::op::Outcome::Changed);
+  }
+}
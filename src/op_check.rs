@@ -0,0 +1,242 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::path::{Path,PathBuf};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck check [options]
+Check the repository and report the result in the Nagios plugin format
+
+Prints a single status line with performance data and exits with one of the
+standard plugin exit codes: 0 (OK), 1 (WARNING), 2 (CRITICAL) or 3 (UNKNOWN).
+
+Thresholds are given as a comma-separated list of KEY:N pairs. The state is
+raised if the value of KEY is greater than N. Valid keys are 'created',
+'deleted', 'modified', 'metadata_modified', 'renamed' and 'age' (the age of the
+latest snapshot in seconds; the suffixes m, h and d are accepted).
+
+options:
+  -w,--warning=LIST      Set the warning thresholds. default:
+                         created:0,deleted:0,metadata_modified:0,renamed:0
+  -c,--critical=LIST     Set the critical thresholds. default: modified:0
+  --full                 Compute the checksums of all files, like 'verify'
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --set_time=TIMESTAMP   Use the specified current unix microsecond timestamp
+                         instead of the real system time
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+const DEFAULT_WARNING : &'static str = "created:0,deleted:0,metadata_modified:0,renamed:0";
+const DEFAULT_CRITICAL : &'static str = "modified:0";
+
+type Thresholds = BTreeMap<String, u64>;
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  return match check(args) {
+    Ok(outcome) => Ok(outcome),
+    Err(e) => {
+      let state = ::op::NagiosState::Unknown;
+      ::prompt::print_check_status(state.name(), &e.message, &vec!());
+      Ok(::op::Outcome::Nagios(state))
+    }
+  };
+}
+
+fn check(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("w", "warning", "warning", "LIST");
+  flag_cfg.optopt("c", "critical", "critical", "LIST");
+  flag_cfg.optflag("", "full", "full");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "set_time", "set_time", "TIMESTAMP");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("check", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(false);
+  ::prompt::set_colours(false);
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let thresholds_warning = parse_thresholds(
+      &flags.opt_str("warning").unwrap_or(DEFAULT_WARNING.into()))
+      .map_err(::op::usage_error)?;
+
+  let thresholds_critical = parse_thresholds(
+      &flags.opt_str("critical").unwrap_or(DEFAULT_CRITICAL.into()))
+      .map_err(::op::usage_error)?;
+
+  let time = match flags.opt_str("set_time").and_then(|x| x.parse::<i64>().ok()) {
+    Some(time) => time,
+    None => {
+      let now = SystemTime::now();
+      match now.duration_since(UNIX_EPOCH) {
+        Ok(v) => v.as_secs() as i64 * 1_000_000 + v.subsec_nanos() as i64 / 1_000,
+        Err(e) => return Err(format!("internal error: {}", e).into()),
+      }
+    }
+  };
+
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+  let snapshot_target_ref = match index.latest() {
+    Some(idx) => idx,
+    None => return Err(::op::index_error(format!("no snapshots"))),
+  };
+
+  let snapshot_target = index.load(&snapshot_target_ref).map_err(::op::index_error)?;
+
+  let scan_opts = ::index_scan::ScanOptions {
+    exclude_paths: vec!(PathBuf::from(&index_path)).into(),
+    exclusive_paths: None,
+    ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+        &flags.opt_strs("exclude"),
+        &flags.opt_strs("include")).map_err(::op::usage_error)?,
+  };
+
+  let mut snapshot_actual = ::index_scan::scan_metadata(
+      &Path::new(&data_path),
+      ::IndexSnapshot::new(snapshot_target.checksum_function.to_owned()),
+      &scan_opts).map_err(::op::unreadable_error)?;
+
+  if !flags.opt_present("full") {
+    snapshot_actual = ::index_scan::copy_checksums(
+        snapshot_actual.to_owned(),
+        &snapshot_target)?;
+  }
+
+  snapshot_actual = ::index_scan::scan_checksums(
+      &Path::new(&data_path),
+      snapshot_actual,
+      &scan_opts).map_err(::op::unreadable_error)?;
+
+  let diff = ::index_diff::diff(&snapshot_target, &snapshot_actual);
+
+  let mut values = Vec::<(&str, u64)>::new();
+  for kind in ::index_diff::DIFF_KINDS.iter() {
+    values.push((kind, ::index_diff::count_kind(&diff, kind)));
+  }
+
+  let age_secs = ((time - snapshot_target_ref.timestamp_us) / 1_000_000).max(0) as u64;
+  values.push(("age", age_secs));
+
+  let exceeds = |thresholds: &Thresholds| values
+      .iter()
+      .any(|&(k, v)| thresholds.get(k).map(|t| v > *t).unwrap_or(false));
+
+  let state = if exceeds(&thresholds_critical) {
+    ::op::NagiosState::Critical
+  } else if exceeds(&thresholds_warning) {
+    ::op::NagiosState::Warning
+  } else {
+    ::op::NagiosState::Ok
+  };
+
+  let changes : Vec<String> = values
+      .iter()
+      .filter(|&&(k, v)| k != "age" && v > 0)
+      .map(|&(k, v)| format!("{} {}", v, k))
+      .collect();
+
+  let summary = format!(
+      "{}, snapshot age {}",
+      if changes.len() == 0 { "no changes".into() } else { changes.join(", ") },
+      format_age(age_secs));
+
+  let mut perfdata = vec!(
+    format!("files={};;;0", snapshot_target.total_file_count()),
+    format!("bytes={}B;;;0", snapshot_target.total_size_bytes()),
+  );
+
+  for &(k, v) in &values {
+    perfdata.push(format!(
+        "{}={}{};{};{};0",
+        k,
+        v,
+        if k == "age" { "s" } else { "" },
+        thresholds_warning.get(k).map(|t| t.to_string()).unwrap_or("".into()),
+        thresholds_critical.get(k).map(|t| t.to_string()).unwrap_or("".into())));
+  }
+
+  ::prompt::print_check_status(state.name(), &summary, &perfdata);
+  return Ok(::op::Outcome::Nagios(state));
+}
+
+fn parse_thresholds(spec: &str) -> Result<Thresholds, ::Error> {
+  let mut thresholds = Thresholds::new();
+
+  for entry in spec.split(',').filter(|e| !e.is_empty()) {
+    let (key, value) = match entry.find(':') {
+      Some(i) => (&entry[..i], &entry[i + 1..]),
+      None => return Err(format!("invalid threshold: {}", entry)),
+    };
+
+    let value = if key == "age" {
+      parse_age(value)?
+    } else if ::index_diff::DIFF_KINDS.contains(&key) {
+      match value.parse::<u64>() {
+        Ok(v) => v,
+        Err(_) => return Err(format!("invalid threshold: {}", entry)),
+      }
+    } else {
+      return Err(format!("invalid threshold key: {}", key));
+    };
+
+    thresholds.insert(key.to_owned(), value);
+  }
+
+  return Ok(thresholds);
+}
+
+fn parse_age(val: &str) -> Result<u64, ::Error> {
+  let (num, factor) = match val.chars().last() {
+    Some('s') => (&val[..val.len() - 1], 1),
+    Some('m') => (&val[..val.len() - 1], 60),
+    Some('h') => (&val[..val.len() - 1], 3600),
+    Some('d') => (&val[..val.len() - 1], 86400),
+    _ => (val, 1),
+  };
+
+  return match num.parse::<u64>().ok().and_then(|v| v.checked_mul(factor)) {
+    Some(v) => Ok(v),
+    None => Err(format!("invalid age: {}", val)),
+  };
+}
+
+fn format_age(secs: u64) -> String {
+  if secs < 60 {
+    return format!("{}s", secs);
+  } else if secs < 3600 {
+    return format!("{}m", secs / 60);
+  } else if secs < 86400 {
+    return format!("{}h", secs / 3600);
+  } else {
+    return format!("{}d", secs / 86400);
+  }
+}
@@ -0,0 +1,123 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::path::{Path,PathBuf};
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck compare [options] <other-dir>
+Compare a copy of the repository against the latest snapshot
+
+The checksums of all files in the other directory are computed and compared to
+the snapshot. Files are reported as missing (only in the snapshot), extra (only
+in the other directory), differing (different contents) or renamed (same
+contents under a different path). Modification times are ignored. Nothing is
+written to either directory.
+
+options:
+  --snapshot=ID          Compare against the specified snapshot instead of the
+                         latest one
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --progress=[on/off]    Turn progress reporting on stderr on or off
+                         default: off
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "progress", "progress", "ONOFF");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("compare", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(flags.opt_str("progress") == Some("on".to_owned()));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let other_path = match flags.free.len() {
+    1 => flags.free[0].to_owned(),
+    0 => return Err(::op::usage_error("need a directory (e.g. 'integritycheck compare /mnt/backup')".into())),
+    _ => return Err(::op::usage_error("too many arguments".into())),
+  };
+
+  if !Path::new(&other_path).is_dir() {
+    return Err(::op::unreadable_error(format!("not a directory: {}", other_path)));
+  }
+
+  ::prompt::print_progress_step(1, 4, "Loading index");
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+
+  let snapshot_ref = match flags.opt_str("snapshot") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots"))),
+    },
+  };
+
+  let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+
+  let scan_opts = ::index_scan::ScanOptions {
+    exclude_paths: vec!(PathBuf::from(&index_path)).into(),
+    exclusive_paths: None,
+    ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+        &flags.opt_strs("exclude"),
+        &flags.opt_strs("include")).map_err(::op::usage_error)?,
+  };
+
+  ::prompt::print_progress_step(2, 4, "Scanning file metadata");
+  let mut snapshot_other = ::index_scan::scan_metadata(
+      &Path::new(&other_path),
+      ::IndexSnapshot::new(snapshot.checksum_function.to_owned()),
+      &scan_opts).map_err(::op::unreadable_error)?;
+
+  ::prompt::print_progress_step(3, 4, "Computing file checksums");
+  snapshot_other = ::index_scan::scan_checksums(
+      &Path::new(&other_path),
+      snapshot_other,
+      &scan_opts).map_err(::op::unreadable_error)?;
+
+  ::prompt::print_progress_step(4, 4, "Comparing files");
+  let result = ::index_compare::compare(&snapshot, &snapshot_other);
+
+  ::prompt::print_progress_complete();
+  ::prompt::print_compare_result(&result);
+
+  if result.differing.len() > 0 {
+    return Ok(::op::Outcome::Corrupted);
+  } else if !result.is_clean() {
+    return Ok(::op::Outcome::Changed);
+  } else {
+    return Ok(::op::Outcome::Clean);
+  }
+}
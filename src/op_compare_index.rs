@@ -0,0 +1,114 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::path::Path;
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck compare-index [options] <other-index-dir>
+Compare the latest snapshots of two index directories without scanning
+
+The contents of both snapshots are compared in the same way as by 'compare'.
+If they differ, the side with the more recent snapshot is reported as the one
+that changed most recently. No files are read except for the index files.
+
+options:
+  --snapshot=ID          Use the specified snapshot instead of the latest one
+  --other_snapshot=ID    Use the specified snapshot from the other index
+                         directory instead of the latest one
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optopt("", "other_snapshot", "other_snapshot", "ID");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("compare-index", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let other_index_path = match flags.free.len() {
+    1 => flags.free[0].to_owned(),
+    0 => return Err(::op::usage_error("need an index directory (e.g. 'integritycheck compare-index /mnt/backup/.ic')".into())),
+    _ => return Err(::op::usage_error("too many arguments".into())),
+  };
+
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+
+  let index_other = ::IndexDirectory::open(
+      &Path::new(""),
+      &Path::new(&other_index_path)).map_err(::op::index_error)?;
+
+  let (snapshot_ref, snapshot) = load_snapshot(&index, flags.opt_str("snapshot"))?;
+  let (snapshot_other_ref, snapshot_other) =
+      load_snapshot(&index_other, flags.opt_str("other_snapshot"))?;
+
+  let checksum_fn = ::checksum::checksum_function_to_str(&snapshot.checksum_function);
+  let checksum_fn_other = ::checksum::checksum_function_to_str(&snapshot_other.checksum_function);
+  if checksum_fn != checksum_fn_other {
+    return Err(::op::usage_error(format!(
+        "the snapshots use different checksum functions ({} and {})",
+        checksum_fn,
+        checksum_fn_other)));
+  }
+
+  let result = ::index_compare::compare(&snapshot, &snapshot_other);
+
+  ::prompt::print_compare_snapshots(
+      &snapshot_ref,
+      &snapshot_other_ref,
+      &other_index_path,
+      result.is_clean());
+
+  ::prompt::print_compare_result(&result);
+
+  if result.differing.len() > 0 {
+    return Ok(::op::Outcome::Corrupted);
+  } else if !result.is_clean() {
+    return Ok(::op::Outcome::Changed);
+  } else {
+    return Ok(::op::Outcome::Clean);
+  }
+}
+
+fn load_snapshot(
+    index: &::IndexDirectory,
+    id: Option<String>) -> Result<(::IndexReference, ::IndexSnapshot), ::op::OperationError> {
+  let snapshot_ref = match id {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots in {:?}", index.path()))),
+    },
+  };
+
+  let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+  return Ok((snapshot_ref, snapshot));
+}
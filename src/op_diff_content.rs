@@ -0,0 +1,147 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::BTreeSet;
+use std::path::{Path,PathBuf};
+use std::str;
+use getopts::Options;
+
+const DIFF_CONTEXT_LINES : usize = 3;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck diff-content [options] [<path>...]
+Display textual diffs of the files that changed between two snapshots
+
+The diffs are computed from the stored file contents (see the --store_content
+option of 'init' and 'ack'). Files whose contents were not stored are listed
+without a diff. By default, the latest snapshot is compared to the one before.
+
+options:
+  --from=ID              Set the old snapshot. default: the snapshot before the
+                         new snapshot
+  --to=ID                Set the new snapshot. default: the latest snapshot
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "from", "from", "ID");
+  flag_cfg.optopt("", "to", "to", "ID");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("diff-content", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let mut prefixes = Vec::<PathBuf>::new();
+  for path in &flags.free {
+    prefixes.push(::op::parse_repository_path(path).map_err(::op::usage_error)?);
+  }
+
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+
+  let snapshot_new_ref = match flags.opt_str("to") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots"))),
+    },
+  };
+
+  let snapshot_old_ref = match flags.opt_str("from") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.list().iter().find(|r| r.timestamp_us < snapshot_new_ref.timestamp_us) {
+      Some(idx) => idx.to_owned(),
+      None => return Err(::op::usage_error(format!(
+          "no snapshot before {}; use --from to choose one",
+          snapshot_new_ref.checksum))),
+    },
+  };
+
+  let snapshot_old = index.load(&snapshot_old_ref).map_err(::op::index_error)?;
+  let snapshot_new = index.load(&snapshot_new_ref).map_err(::op::index_error)?;
+
+  let paths : BTreeSet<&String> = snapshot_old
+      .files
+      .keys()
+      .chain(snapshot_new.files.keys())
+      .filter(|p| prefixes.len() == 0 || prefixes.iter().any(|prefix| Path::new(p).starts_with(prefix)))
+      .collect();
+
+  for path in paths {
+    let checksum_old = snapshot_old.get(path).and_then(|f| f.checksum.as_ref());
+    let checksum_new = snapshot_new.get(path).and_then(|f| f.checksum.as_ref());
+    if checksum_old == checksum_new {
+      continue;
+    }
+
+    let label_old = match checksum_old {
+      Some(_) => format!("a/{}", path),
+      None => "/dev/null".to_owned(),
+    };
+
+    let label_new = match checksum_new {
+      Some(_) => format!("b/{}", path),
+      None => "/dev/null".to_owned(),
+    };
+
+    let data_old = load_contents(&index, &snapshot_old, path)?;
+    let data_new = load_contents(&index, &snapshot_new, path)?;
+    let lines = match (data_old, data_new) {
+      (Some(old), Some(new)) => match (str::from_utf8(&old), str::from_utf8(&new)) {
+        (Ok(old), Ok(new)) if !old.contains('\0') && !new.contains('\0') => {
+          match ::text_diff::unified_diff(old, new, DIFF_CONTEXT_LINES) {
+            Some(lines) => lines,
+            None => vec!("Files are too large to compare".to_owned()),
+          }
+        },
+        _ => vec!("Binary files differ".to_owned()),
+      },
+      _ => vec!("Contents were not stored".to_owned()),
+    };
+
+    ::prompt::print_text_diff(&label_old, &label_new, &lines);
+  }
+
+  return Ok(::op::Outcome::Clean);
+}
+
+/**
+ * Load the stored contents of a file; a file that does not exist in the
+ * snapshot is treated as empty
+ */
+fn load_contents(
+    index: &::IndexDirectory,
+    snapshot: &::IndexSnapshot,
+    path: &str) -> Result<Option<Vec<u8>>, ::op::OperationError> {
+  if snapshot.get(path).is_none() {
+    return Ok(Some(vec!()));
+  }
+
+  return ::content_store::load_file(index, snapshot, path).map_err(::op::index_error);
+}
@@ -0,0 +1,95 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::path::Path;
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck duplicates [options]
+Report files with identical contents in the latest snapshot
+
+This command only reads the index; it never deletes or modifies any files.
+
+options:
+  --min_size=BYTES       Only report files of at least this size. Accepts
+                         the suffixes K, M, G and T. default: 1
+  --max_size=BYTES       Only report files of at most this size
+  --format=FORMAT        Set the output format. Possible values:
+                         'text' (default), 'json', 'jsonl'
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --progress=[on/off]    Turn progress reporting on stderr on or off
+                         default: off
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "min_size", "min_size", "BYTES");
+  flag_cfg.optopt("", "max_size", "max_size", "BYTES");
+  flag_cfg.optopt("", "format", "format", "FORMAT");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "progress", "progress", "ONOFF");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("duplicates", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(flags.opt_str("progress") == Some("on".to_owned()));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let min_size = match flags.opt_str("min_size") {
+    Some(v) => ::prompt::parse_bytecount(&v).map_err(::op::usage_error)?,
+    None => 1,
+  };
+
+  let max_size = match flags.opt_str("max_size") {
+    Some(v) => Some(::prompt::parse_bytecount(&v).map_err(::op::usage_error)?),
+    None => None,
+  };
+
+  let format = ::prompt::parse_output_format(flags.opt_str("format"))
+      .map_err(::op::usage_error)?;
+
+  ::prompt::print_progress_step(1, 2, "Loading index");
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+  let snapshot = match index.latest() {
+    Some(idx) => index.load(&idx).map_err(::op::index_error)?,
+    None => return Err(::op::index_error(format!("no snapshots"))),
+  };
+
+  ::prompt::print_progress_step(2, 2, "Finding duplicates");
+  let duplicates = ::index_duplicates::find_duplicates(&snapshot, min_size, max_size);
+
+  ::prompt::print_progress_complete();
+
+  if format == ::prompt::OutputFormat::Text {
+    ::prompt::print_repository_path(&data_path);
+    ::prompt::print_duplicates(&duplicates);
+  } else {
+    ::prompt::print_duplicates_json(&format, &duplicates);
+  }
+
+  return Ok(::op::Outcome::Clean);
+}
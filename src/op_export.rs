@@ -0,0 +1,86 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck export [options]
+Write a snapshot in the format of another checksum tool
+
+The output can be verified without integritycheck by running e.g. 'sha256sum -c'
+or 'shasum -c' in the repository root. The 'mtree' format takes the mode and
+owner of each file from the repository, since they are not stored in snapshots.
+
+options:
+  --format=FORMAT        Set the output format. Possible values:
+                         'sha256sum' (default), 'md5sum', 'bsd-tag', 'mtree'
+  --snapshot=ID          Use the specified snapshot instead of the latest one
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --progress=[on/off]    Turn progress reporting on stderr on or off
+                         default: off
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "format", "format", "FORMAT");
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "progress", "progress", "ONOFF");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("export", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(flags.opt_str("progress") == Some("on".to_owned()));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+  let format = ::index_export::parse_export_format(
+      &flags.opt_str("format").unwrap_or("sha256sum".into()))
+      .map_err(::op::usage_error)?;
+
+  ::prompt::print_progress_step(1, 1, "Loading index");
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+  let snapshot_ref = match flags.opt_str("snapshot") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots"))),
+    },
+  };
+
+  let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+  let data = ::index_export::export(&snapshot, &format, &Path::new(&data_path)).map_err(::op::usage_error)?;
+
+  ::prompt::print_progress_complete();
+
+  if let Err(e) = io::stdout().write_all(data.as_bytes()) {
+    return Err(e.to_string().into());
+  }
+
+  return Ok(::op::Outcome::Clean);
+}
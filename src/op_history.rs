@@ -14,6 +14,8 @@ usage: integritycheck log [options]
 Display a historical log of snapshots and changes to the repository
 
 options:
+  --format=FORMAT        Set the output format. Possible values:
+                         'text' (default), 'json', 'jsonl'
   -d,--data_dir=PATH     Set the path of the repository/data directory
                          default: '.'
   -x,--index_dir=PATH    Set the path of the index directory. Note that this
@@ -27,17 +29,18 @@ options:
   -h,--help              Print this help message and exit
 ";
 
-pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
   let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "format", "format", "FORMAT");
   flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
   flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
   flag_cfg.optopt("", "progress", "progress", "ONOFF");
   flag_cfg.optopt("", "colours", "progress", "ONOFF");
   flag_cfg.optflag("v", "verbose", "verbose");
 
-  let flags = match flag_cfg.parse(args) {
+  let flags = match ::config::parse_flags("log", &flag_cfg, args) {
     Ok(f) => f,
-    Err(e) => return Err(e.to_string()),
+    Err(e) => return Err(::op::usage_error(e)),
   };
 
   ::prompt::set_debug(flags.opt_present("verbose"));
@@ -46,12 +49,20 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
 
   let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
   let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+  let format = ::prompt::parse_output_format(flags.opt_str("format"))
+      .map_err(::op::usage_error)?;
 
   ::prompt::print_progress_step(1, 1, "Loading index");
-  let index = ::IndexDirectory::open(&Path::new(&data_path), &Path::new(&index_path))?;
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
 
   ::prompt::print_progress_complete();
-  ::prompt::print_snapshot_table(&index)?;
+  if format == ::prompt::OutputFormat::Text {
+    ::prompt::print_snapshot_table(&index)?;
+  } else {
+    ::prompt::print_snapshot_table_json(&format, &data_path, &index)?;
+  }
 
-  return Ok(true);
+  return Ok(::op::Outcome::Clean);
 }
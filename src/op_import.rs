@@ -0,0 +1,175 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path,PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck import [options] <manifest>
+Create a new snapshot from the manifest of another checksum tool
+
+The checksums are taken from the manifest and the size and modification time
+of each file from the repository, so no file contents are read. Run 'verify'
+afterwards to check the imported checksums. Use '-' to read the manifest from
+stdin. Paths in the manifest are relative to the repository root.
+
+SFV manifests only contain CRC-32 checksums, which detect accidental damage but
+not deliberate changes. All later snapshots of the repository keep using CRC-32;
+to switch to sha256 once 'verify' has confirmed the imported checksums, remove
+the index and create a new one with 'init'.
+
+options:
+  --format=FORMAT        Set the manifest format. Possible values:
+                         'sha256sum' (default), 'md5sum', 'hashdeep', 'sfv',
+                         'mtree'
+  -m,--message=MSG       Set a message to be stored along with the snapshot
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. The index is created if it does
+                         not exist. default: '.ic'
+  --progress=[on/off]    Turn progress reporting on stderr on or off
+                         default: on
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  --set_time=TIMESTAMP   Use the specified current unix microsecond timestamp
+                         instead of the real system time
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "format", "format", "FORMAT");
+  flag_cfg.optopt("m", "message", "message", "MSG");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "progress", "progress", "ONOFF");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optopt("", "set_time", "set_time", "TIMESTAMP");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("import", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(flags.opt_str("progress") != Some("off".to_owned()));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+  let format = ::index_import::parse_import_format(
+      &flags.opt_str("format").unwrap_or("sha256sum".into()))
+      .map_err(::op::usage_error)?;
+
+  let manifest_path = match flags.free.len() {
+    1 => flags.free[0].to_owned(),
+    0 => return Err(::op::usage_error("need a manifest (e.g. 'integritycheck import SHA256SUMS')".into())),
+    _ => return Err(::op::usage_error("too many arguments".into())),
+  };
+
+  let time = match flags.opt_str("set_time").and_then(|x| x.parse::<i64>().ok()) {
+    Some(time) => time,
+    None => {
+      let now = SystemTime::now();
+      match now.duration_since(UNIX_EPOCH) {
+        Ok(v) => v.as_secs() as i64 * 1_000_000 + v.subsec_nanos() as i64 / 1_000,
+        Err(e) => return Err(format!("internal error: {}", e).into()),
+      }
+    }
+  };
+
+  let data_path_abs = match fs::canonicalize(&data_path) {
+    Ok(p) => p,
+    Err(e) => return Err(::op::unreadable_error(e.to_string())),
+  };
+
+  ::prompt::print_progress_step(1, 4, "Reading manifest");
+  let manifest = ::index_import::read_manifest(&manifest_path, &format)
+      .map_err(::op::usage_error)?;
+
+  ::prompt::print_progress_step(2, 4, "Loading index");
+  let mut index = if Path::new(&data_path).join(&index_path).exists() {
+    ::IndexDirectory::open(&Path::new(&data_path), &Path::new(&index_path))
+  } else {
+    ::IndexDirectory::create(&Path::new(&data_path), &Path::new(&index_path))
+  }.map_err(::op::index_error)?;
+
+  ::prompt::print_progress_step(3, 4, "Scanning file metadata");
+  let snapshot_live = ::index_scan::scan_metadata(
+      &Path::new(&data_path),
+      ::IndexSnapshot::new(manifest.checksum_function.to_owned()),
+      &::index_scan::ScanOptions {
+        exclude_paths: vec!(PathBuf::from(&index_path)).into(),
+        exclusive_paths: None,
+        ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+            &flags.opt_strs("exclude"),
+            &flags.opt_strs("include")).map_err(::op::usage_error)?,
+      }).map_err(::op::unreadable_error)?;
+
+  let mut snapshot = ::IndexSnapshot::new(manifest.checksum_function.to_owned());
+  let mut listed = HashSet::<String>::new();
+  let mut missing = Vec::<String>::new();
+
+  for &(ref path, ref checksum) in &manifest.entries {
+    let path = ::index_import::normalize_path(path, &data_path_abs);
+    listed.insert(path.to_owned());
+
+    let mut file_info = match snapshot_live.get(&path) {
+      Some(v) => v.to_owned(),
+      None => {
+        missing.push(path);
+        continue;
+      },
+    };
+
+    file_info.checksum = Some(checksum.to_owned());
+    snapshot.update(&path, &file_info);
+  }
+
+  let unlisted : Vec<String> = snapshot_live
+      .list()
+      .into_iter()
+      .filter(|p| !listed.contains(p))
+      .collect();
+
+  snapshot.message = Some(
+      flags.opt_str("message").unwrap_or(format!("imported from {}", manifest_path)));
+
+  ::prompt::print_progress_step(4, 4, "Committing new snapshot");
+  let snapshot_ref = index.append(&snapshot, time).map_err(::op::index_error)?;
+
+  ::prompt::print_progress_complete();
+  ::prompt::print_repository_path(&data_path);
+  ::prompt::print_import_report(snapshot.total_file_count(), &missing, &unlisted);
+  ::prompt::print_success(&format!("Created snapshot {:?}", snapshot_ref.checksum));
+
+  if let ::checksum::ChecksumFunction::CRC32 = snapshot.checksum_function {
+    ::prompt::print_warning(
+        "CRC-32 is not an integrity hash and is kept for all later snapshots; \
+         see 'integritycheck import --help' for how to switch to sha256");
+  }
+
+  if missing.len() + unlisted.len() > 0 {
+    return Ok(::op::Outcome::Changed);
+  } else {
+    return Ok(::op::Outcome::Clean);
+  }
+}
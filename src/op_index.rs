@@ -6,20 +6,29 @@
  * licensed under the Apache License, Version 2.0 (the "License"); you may not
  * use this file except in compliance with the License.
  */
-use std::path::{Path,PathBuf};
+use std::path::{Component,Path,PathBuf};
 use std::fs;
 use std::io;
-use std::io::Write;
+use std::io::{Read,Write};
 use getopts::Options;
 
 pub const USAGE : &'static str = "\
 usage: integritycheck index [options] <path>...
-Create an index file containing checksums of all files in a directory
-Compare the current state of the repository to the latest snapshot
+Create an index file containing checksums of all files in one or more
+directories. The paths in the listing are qualified with the directory given
+on the command line (e.g. 'a/file' for 'integritycheck index a/')
 
 options:
+  --files-from=FILE      Also add the files listed in FILE (one path per line)
+                         to the listing. Use '-' to read the list from stdin
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
   -d,--data_dir=PATH     Set the path of the repository/data directory
                          default: '.'
+  --format=FORMAT        Set the output format. Possible values:
+                         'text' (default), 'json', 'jsonl'
   --progress=[on/off]    Turn progress reporting on stderr on or off
                          default: off
   --colours=[on/off]     Turn coloured terminal output on or off
@@ -28,52 +37,111 @@ options:
   -h,--help              Print this help message and exit
 ";
 
-pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
   let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "format", "format", "FORMAT");
+  flag_cfg.optopt("", "files-from", "files-from", "FILE");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
   flag_cfg.optopt("", "progress", "progress", "ONOFF");
   flag_cfg.optopt("", "colours", "progress", "ONOFF");
   flag_cfg.optflag("v", "verbose", "verbose");
 
-  let flags = match flag_cfg.parse(args) {
+  let flags = match ::config::parse_flags("index", &flag_cfg, args) {
     Ok(f) => f,
-    Err(e) => return Err(e.to_string()),
+    Err(e) => return Err(::op::usage_error(e)),
   };
 
   ::prompt::set_debug(flags.opt_present("verbose"));
   ::prompt::set_progress(flags.opt_str("progress") != Some("off".to_owned()));
   ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
 
+  let format = ::prompt::parse_output_format(flags.opt_str("format"))
+      .map_err(::op::usage_error)?;
+
   let mut pathspecs = Vec::<PathBuf>::new();
   for pathspec in &flags.free {
     pathspecs.push(PathBuf::from(pathspec));
   }
 
-  if pathspecs.len() == 0 {
-    return Err("need a path (e.g. 'integritycheck index .')".into());
+  let file_list = match flags.opt_str("files-from") {
+    Some(list_path) => read_file_list(&list_path).map_err(::op::unreadable_error)?,
+    None => vec!(),
+  };
+
+  if pathspecs.len() == 0 && !flags.opt_present("files-from") {
+    return Err(::op::usage_error("need a path (e.g. 'integritycheck index .')".into()));
   }
 
+  let scan_opts = ::index_scan::ScanOptions {
+    exclude_paths: ::path_trie::PathTrie::new(),
+    exclusive_paths: None,
+    ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+        &flags.opt_strs("exclude"),
+        &flags.opt_strs("include")).map_err(::op::usage_error)?,
+  };
+
   ::prompt::print_progress_step(1, 2, "Scanning file metadata");
-  let mut snapshot = ::index_scan::scan_metadata(
-      &Path::new(&pathspecs.get(0).unwrap()), // FIXME
-      ::IndexSnapshot::new(::checksum::ChecksumFunction::SHA256),
-      &::index_scan::ScanOptions {
-        exclude_paths: vec!(),
-        exclusive_paths: None,
-      })?;
+  let mut snapshot = ::index_scan::scan_metadata_list(
+      &file_list,
+      ::IndexSnapshot::new(::checksum::ChecksumFunction::SHA256))
+      .map_err(::op::unreadable_error)?;
+
+  for pathspec in &pathspecs {
+    let pathspec_snapshot = ::index_scan::scan_metadata(
+        &pathspec,
+        ::IndexSnapshot::new(::checksum::ChecksumFunction::SHA256),
+        &scan_opts).map_err(::op::unreadable_error)?;
+
+    let prefix = pathspec
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect::<PathBuf>();
+
+    for (file_path, file_info) in &pathspec_snapshot.files {
+      match prefix.join(file_path).to_str() {
+        Some(p) => snapshot.update(p, file_info),
+        None => return Err(::op::unreadable_error(format!("invalid path"))),
+      };
+    }
+  }
 
   ::prompt::print_progress_step(2, 2, "Computing checksums");
   snapshot = ::index_scan::scan_checksums(
-      &Path::new(&pathspecs.get(0).unwrap()), // FIXME
+      &Path::new(""),
       snapshot.to_owned(),
-      &::index_scan::ScanOptions {
-        exclude_paths: vec!(),
-        exclusive_paths: None
-      })?;
+      &scan_opts).map_err(::op::unreadable_error)?;
 
   ::prompt::print_progress_complete();
+
+  if format != ::prompt::OutputFormat::Text {
+    ::prompt::print_file_list_json(&format, &snapshot);
+    return Ok(::op::Outcome::Clean);
+  }
+
   ::prompt::print_repository_size(&snapshot);
 
   io::stdout().write(snapshot.unparse().as_bytes());
 
-  return Ok(true);
+  return Ok(::op::Outcome::Clean);
+}
+
+fn read_file_list(list_path: &str) -> Result<Vec<PathBuf>, ::Error> {
+  let mut data = String::new();
+  let result = if list_path == "-" {
+    io::stdin().read_to_string(&mut data)
+  } else {
+    fs::File::open(list_path).and_then(|mut f| f.read_to_string(&mut data))
+  };
+
+  if let Err(e) = result {
+    return Err(format!("error while reading file list {:?}: {}", list_path, e));
+  }
+
+  return Ok(
+      data
+          .lines()
+          .filter(|l| !l.is_empty())
+          .map(|l| PathBuf::from(l))
+          .collect());
 }
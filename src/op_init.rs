@@ -18,6 +18,12 @@ options:
   --checksum=TYPE        Set the checksum/digest function. Possible values:
                          'sha256' (default), 'md5'
   --empty                Create the index, but do not add the existing files
+  --store_content=SIZE   Store a copy of every file that is no larger than SIZE
+                         (e.g. '64K') in the index directory. default: off
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
   -d,--data_dir=PATH     Set the path of the repository/data directory
                          default: '.'
   -x,--index_dir=PATH    Set the path of the index directory. Note that this
@@ -33,10 +39,13 @@ options:
   -h,--help              Print this help message and exit
 ";
 
-pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
   let mut flag_cfg = Options::new();
   flag_cfg.optopt("", "checksum", "checksum", "FUNCTION");
   flag_cfg.optflag("", "empty", "empty");
+  flag_cfg.optopt("", "store_content", "store_content", "SIZE");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
   flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
   flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
   flag_cfg.optopt("", "progress", "progress", "ONOFF");
@@ -44,9 +53,9 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
   flag_cfg.optopt("", "set_time", "set_time", "TIMESTAMP");
   flag_cfg.optflag("v", "verbose", "verbose");
 
-  let flags = match flag_cfg.parse(args) {
+  let flags = match ::config::parse_flags("init", &flag_cfg, args) {
     Ok(f) => f,
-    Err(e) => return Err(e.to_string()),
+    Err(e) => return Err(::op::usage_error(e)),
   };
 
   ::prompt::set_debug(flags.opt_present("verbose"));
@@ -56,7 +65,13 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
   let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
   let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
   let checksum_fn = ::checksum::checksum_function_from_str(
-      &flags.opt_str("checksum").unwrap_or(::DEFAULT_CHECKSUM_FUNCTION.into()))?;
+      &flags.opt_str("checksum").unwrap_or(::DEFAULT_CHECKSUM_FUNCTION.into()))
+      .map_err(::op::usage_error)?;
+  let store_content = match flags.opt_str("store_content") {
+    Some(ref v) if v == "off" => None,
+    Some(v) => Some(::prompt::parse_bytecount(&v).map_err(::op::usage_error)?),
+    None => None,
+  };
 
   let time = match flags.opt_str("set_time").and_then(|x| x.parse::<i64>().ok()) {
     Some(time) => time,
@@ -64,7 +79,7 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
       let now = SystemTime::now();
       match now.duration_since(UNIX_EPOCH) {
         Ok(v) => v.as_secs() as i64 * 1_000_000 + v.subsec_nanos() as i64 / 1_000,
-        Err(e) => return Err(format!("internal error: {}", e)),
+        Err(e) => return Err(format!("internal error: {}", e).into()),
       }
     }
   };
@@ -72,12 +87,15 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
   ::prompt::print_progress_step(1, 4, "Creating index");
   let mut index = ::IndexDirectory::create(
       &Path::new(&data_path),
-      &Path::new(&index_path))?;
+      &Path::new(&index_path)).map_err(::op::index_error)?;
 
   ::prompt::print_progress_step(2, 4, "Scanning file metadata");
   let scan_opts = ::index_scan::ScanOptions {
-    exclude_paths: vec!(PathBuf::from(&index_path)),
+    exclude_paths: vec!(PathBuf::from(&index_path)).into(),
     exclusive_paths: None,
+    ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+        &flags.opt_strs("exclude"),
+        &flags.opt_strs("include")).map_err(::op::usage_error)?,
   };
 
   let mut snapshot = ::IndexSnapshot::new(checksum_fn);
@@ -85,7 +103,7 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
     snapshot = ::index_scan::scan_metadata(
         &Path::new(&data_path),
         snapshot,
-        &scan_opts)?;
+        &scan_opts).map_err(::op::unreadable_error)?;
   }
 
   ::prompt::print_progress_step(3, 4, "Computing file checksums");
@@ -93,11 +111,19 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
     snapshot = ::index_scan::scan_checksums(
         &Path::new(&data_path),
         snapshot,
-        &scan_opts)?;
+        &scan_opts).map_err(::op::unreadable_error)?;
   }
 
   ::prompt::print_progress_step(4, 4, "Committing new snapshot");
-  let snapshot_ref = index.append(&snapshot, time)?;
+  if let Some(max_size) = store_content {
+    ::content_store::store(
+        &index,
+        &Path::new(&data_path),
+        &snapshot,
+        max_size).map_err(::op::index_error)?;
+  }
+
+  let snapshot_ref = index.append(&snapshot, time).map_err(::op::index_error)?;
 
   ::prompt::print_progress_complete();
   ::prompt::print_repository_path(&data_path);
@@ -107,5 +133,5 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
     ::prompt::print_repository_status(true);
   }
 
-  return Ok(true);
+  return Ok(::op::Outcome::Clean);
 }
@@ -0,0 +1,88 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::path::{Path,PathBuf};
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck ls-files [options] [<path>]
+List the files recorded in a snapshot
+
+Prints the checksum, size in bytes, modification time and path of every file
+in the snapshot. The path is relative to the repository root. Newlines, spaces
+and backslashes in paths are escaped as '\\n', '\\_' and '\\\\'.
+
+options:
+  --snapshot=ID          Use the specified snapshot instead of the latest one
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --progress=[on/off]    Turn progress reporting on stderr on or off
+                         default: off
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "progress", "progress", "ONOFF");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("ls-files", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(flags.opt_str("progress") == Some("on".to_owned()));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  if flags.free.len() > 1 {
+    return Err(::op::usage_error("too many paths".into()));
+  }
+
+  let prefix = match flags.free.get(0) {
+    Some(p) => ::op::parse_repository_path(p).map_err(::op::usage_error)?,
+    None => PathBuf::new(),
+  };
+
+  ::prompt::print_progress_step(1, 1, "Loading index");
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+  let snapshot_ref = match flags.opt_str("snapshot") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots"))),
+    },
+  };
+
+  let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+  let files = snapshot
+      .files
+      .iter()
+      .filter(|&(fpath, _)| Path::new(fpath).starts_with(&prefix))
+      .collect();
+
+  ::prompt::print_progress_complete();
+  ::prompt::print_file_list(&files);
+
+  return Ok(::op::Outcome::Clean);
+}
@@ -0,0 +1,180 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::fs;
+use std::io::Write;
+use std::path::{Path,PathBuf};
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck protect [options] [<path>...]
+Compute recovery data for the files in a snapshot
+
+Reed-Solomon recovery blocks are computed for every file and stored in the
+'parity' directory in the index directory. A corrupted file can later be
+reconstructed with 'repair' as long as no more of its blocks are damaged than
+there are intact recovery blocks. Files are only protected if their contents
+match the snapshot. Files whose contents are already protected are skipped.
+
+options:
+  --redundancy=PERCENT   Set the number of recovery blocks per file in percent
+                         of the number of data blocks (1-100). default: 10%
+  --block_size=BYTES     Set the minimum block size. Files are split into at
+                         most 128 blocks. default: 4K
+  --snapshot=ID          Protect the files of the specified snapshot instead
+                         of the latest one
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --progress=[on/off]    Turn progress reporting on stderr on or off
+                         default: on
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "redundancy", "redundancy", "PERCENT");
+  flag_cfg.optopt("", "block_size", "block_size", "BYTES");
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "progress", "progress", "ONOFF");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("protect", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(flags.opt_str("progress") != Some("off".to_owned()));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let redundancy = match flags.opt_str("redundancy") {
+    Some(v) => match v.trim_end_matches('%').parse::<u64>() {
+      Ok(v) if v >= 1 && v <= 100 => v,
+      _ => return Err(::op::usage_error(format!("invalid redundancy: {}", v))),
+    },
+    None => ::parity::DEFAULT_REDUNDANCY,
+  };
+
+  let block_size = match flags.opt_str("block_size") {
+    Some(v) => match ::prompt::parse_bytecount(&v).map_err(::op::usage_error)? {
+      0 => return Err(::op::usage_error(format!("invalid block size: {}", v))),
+      v => v,
+    },
+    None => ::parity::DEFAULT_BLOCK_SIZE,
+  };
+
+  let mut prefixes = Vec::<PathBuf>::new();
+  for path in &flags.free {
+    prefixes.push(::op::parse_repository_path(path).map_err(::op::usage_error)?);
+  }
+
+  ::prompt::print_progress_step(1, 2, "Loading index");
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+
+  let snapshot_ref = match flags.opt_str("snapshot") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots"))),
+    },
+  };
+
+  let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+
+  let parity_path = index.parity_path();
+  if let Err(e) = fs::create_dir_all(&parity_path) {
+    return Err(::op::index_error(format!("error while creating parity directory: {}", e)));
+  }
+
+  let files : Vec<(&String, &::IndexFileInfo)> = snapshot
+      .files
+      .iter()
+      .filter(|&(fpath, _)| prefixes.len() == 0 || prefixes.iter().any(|p| Path::new(fpath).starts_with(p)))
+      .filter(|&(_, finfo)| finfo.size_bytes > 0)
+      .collect();
+
+  ::prompt::print_progress_step(2, 2, "Computing recovery data");
+  let files_total = files.len() as u64;
+  let bytes_total = files.iter().fold(0, |acc, &(_, finfo)| acc + finfo.size_bytes);
+  let mut files_scanned = 0;
+  let mut bytes_scanned = 0;
+  let mut protected = 0;
+  let mut skipped = 0;
+  let mut parity_bytes = 0;
+  let mut mismatched = Vec::<String>::new();
+
+  for (fpath, finfo) in files {
+    files_scanned += 1;
+    bytes_scanned += finfo.size_bytes;
+    ::prompt::print_scanprogress(files_scanned, bytes_scanned, files_total, bytes_total);
+
+    let checksum = match finfo.checksum {
+      Some(ref c) => c,
+      None => continue,
+    };
+
+    let parity_file_path = parity_path.join(format!("{}{}", checksum, ::parity::PARITY_FILE_SUFFIX));
+    if parity_file_path.exists() {
+      ::prompt::print_debug(&format!("Skipping {:?}; already protected", fpath));
+      skipped += 1;
+      continue;
+    }
+
+    let file_path = Path::new(&data_path).join(fpath);
+    let checksum_actual = ::checksum::compute_file(
+        snapshot.checksum_function.to_owned(),
+        &file_path).ok();
+
+    if checksum_actual.as_ref() != Some(checksum) {
+      mismatched.push(fpath.to_owned());
+      continue;
+    }
+
+    ::prompt::print_debug(&format!("Computing recovery data for {:?}", fpath));
+    let parity = ::parity::compute(&file_path, block_size, redundancy)
+        .map_err(::op::unreadable_error)?;
+
+    let data = parity.encode();
+    let parity_file_path_tmp = parity_file_path.with_extension("par.tmp");
+    let result =
+        fs::File::create(&parity_file_path_tmp)
+        .and_then(|mut f| f.write_all(&data).and_then(|_| f.sync_all()))
+        .and_then(|_| fs::rename(&parity_file_path_tmp, &parity_file_path));
+
+    if let Err(e) = result {
+      return Err(::op::index_error(format!("error while writing parity file: {}", e)));
+    }
+
+    protected += 1;
+    parity_bytes += data.len() as u64;
+  }
+
+  ::prompt::print_scanprogress_complete();
+  ::prompt::print_progress_complete();
+  ::prompt::print_protect_result(protected, skipped, parity_bytes, &mismatched);
+
+  if mismatched.len() > 0 {
+    return Ok(::op::Outcome::Changed);
+  } else {
+    return Ok(::op::Outcome::Clean);
+  }
+}
@@ -0,0 +1,236 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::fs;
+use std::path::{Path,PathBuf};
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck recover [options] --from=<backup-dir> --to=<staging-dir> [<path>...]
+Locate good copies of deleted or corrupted files in a backup
+
+By default, a full check of the repository is performed to find the files that
+were deleted or whose contents were modified. If paths are given, all files
+from the snapshot under these paths are recovered instead. For each file, the
+backup is searched for a copy with the expected checksum; a file at the same
+path is preferred, otherwise a matching file anywhere in the backup is used.
+The copies are written to the staging directory and verified. The repository
+itself is never modified.
+
+options:
+  --from=PATH            Search for copies in the backup directory at PATH
+  --to=PATH              Write the recovered files to the staging directory at
+                         PATH. The directory is created if it does not exist
+  --snapshot=ID          Recover the files as recorded in the specified
+                         snapshot instead of the latest one
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --progress=[on/off]    Turn progress reporting on stderr on or off
+                         default: on
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "from", "from", "PATH");
+  flag_cfg.optopt("", "to", "to", "PATH");
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "progress", "progress", "ONOFF");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("recover", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_progress(flags.opt_str("progress") != Some("off".to_owned()));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let backup_path = match flags.opt_str("from") {
+    Some(p) => p,
+    None => return Err(::op::usage_error("need a backup directory (--from=PATH)".into())),
+  };
+
+  let staging_path = match flags.opt_str("to") {
+    Some(p) => p,
+    None => return Err(::op::usage_error("need a staging directory (--to=PATH)".into())),
+  };
+
+  let mut prefixes = Vec::<PathBuf>::new();
+  for path in &flags.free {
+    prefixes.push(::op::parse_repository_path(path).map_err(::op::usage_error)?);
+  }
+
+  if !Path::new(&backup_path).is_dir() {
+    return Err(::op::unreadable_error(format!("not a directory: {}", backup_path)));
+  }
+
+  let data_path_abs = match fs::canonicalize(&data_path) {
+    Ok(p) => p,
+    Err(e) => return Err(::op::unreadable_error(e.to_string())),
+  };
+
+  let staging_path_abs = staging_path_abs(&Path::new(&staging_path))
+      .map_err(::op::unreadable_error)?;
+
+  if staging_path_abs.starts_with(&data_path_abs) {
+    return Err(::op::usage_error("the staging directory must be outside of the repository".into()));
+  }
+
+  if let Err(e) = fs::create_dir_all(&staging_path) {
+    return Err(format!("error while creating staging directory: {}", e).into());
+  }
+
+  ::prompt::print_progress_step(1, 5, "Loading index");
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+
+  let snapshot_ref = match flags.opt_str("snapshot") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots"))),
+    },
+  };
+
+  let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+
+  let scan_opts = ::index_scan::ScanOptions {
+    exclude_paths: vec!(PathBuf::from(&index_path)).into(),
+    exclusive_paths: None,
+    ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+        &flags.opt_strs("exclude"),
+        &flags.opt_strs("include")).map_err(::op::usage_error)?,
+  };
+
+  let targets : Vec<String> = if prefixes.len() > 0 {
+    ::prompt::print_progress_step(2, 5, "Selecting files");
+    snapshot
+        .list()
+        .into_iter()
+        .filter(|f| prefixes.iter().any(|p| Path::new(f).starts_with(p)))
+        .collect()
+  } else {
+    ::prompt::print_progress_step(2, 5, "Checking repository");
+    let mut snapshot_actual = ::index_scan::scan_metadata(
+        &Path::new(&data_path),
+        ::IndexSnapshot::new(snapshot.checksum_function.to_owned()),
+        &scan_opts).map_err(::op::unreadable_error)?;
+
+    snapshot_actual = ::index_scan::scan_checksums(
+        &Path::new(&data_path),
+        snapshot_actual,
+        &scan_opts).map_err(::op::unreadable_error)?;
+
+    ::index_diff::diff(&snapshot, &snapshot_actual)
+        .iter()
+        .filter_map(|d| match d {
+          &::index_diff::IndexDiff::Deleted{ref file} => file.to_str(),
+          &::index_diff::IndexDiff::Modified{ref file} => file.to_str(),
+          _ => None,
+        })
+        .map(|f| f.to_owned())
+        .collect()
+  };
+
+  ::prompt::print_progress_step(3, 5, "Scanning backup metadata");
+  let backup = ::index_scan::scan_metadata(
+      &Path::new(&backup_path),
+      ::IndexSnapshot::new(snapshot.checksum_function.to_owned()),
+      &scan_opts).map_err(::op::unreadable_error)?;
+
+  ::prompt::print_progress_step(4, 5, "Computing checksums of candidate files");
+  let backup = ::index_scan::scan_checksums(
+      &Path::new(&backup_path),
+      ::index_recover::candidates(&snapshot, &targets, &backup),
+      &scan_opts).map_err(::op::unreadable_error)?;
+
+  let mut result = ::index_recover::locate(&snapshot, &targets, &backup);
+
+  ::prompt::print_progress_step(5, 5, "Copying files");
+  let mut recovered = Vec::<(String, String)>::new();
+  for (target, source) in result.found {
+    let source_path = Path::new(&backup_path).join(&source);
+    let staged_path = Path::new(&staging_path).join(&target);
+
+    if let Some(parent) = staged_path.parent() {
+      if let Err(e) = fs::create_dir_all(parent) {
+        return Err(format!("error while creating directory {:?}: {}", parent, e).into());
+      }
+    }
+
+    if let Err(e) = fs::copy(&source_path, &staged_path) {
+      return Err(format!("error while copying {:?}: {}", source_path, e).into());
+    }
+
+    let checksum = ::checksum::compute_file(
+        snapshot.checksum_function.to_owned(),
+        &staged_path).map_err(::op::unreadable_error)?;
+
+    if Some(&checksum) == snapshot.get(&target).and_then(|f| f.checksum.as_ref()) {
+      recovered.push((target, source));
+    } else {
+      ::prompt::print_warning(&format!("the copy of {:?} failed to verify", target));
+      fs::remove_file(&staged_path).ok();
+      result.not_found.push(target);
+    }
+  }
+
+  result.found = recovered;
+  result.not_found.sort();
+
+  ::prompt::print_progress_complete();
+  ::prompt::print_recover_result(&result, &staging_path);
+
+  if result.not_found.len() > 0 {
+    return Ok(::op::Outcome::Changed);
+  } else {
+    return Ok(::op::Outcome::Clean);
+  }
+}
+
+/**
+ * Resolve the staging directory, which may not exist yet, to an absolute path
+ */
+fn staging_path_abs(path: &Path) -> Result<PathBuf, ::Error> {
+  if path.exists() {
+    return fs::canonicalize(path).map_err(|e| e.to_string());
+  }
+
+  let parent = match path.parent() {
+    Some(p) if !p.as_os_str().is_empty() => p,
+    _ => Path::new("."),
+  };
+
+  let file_name = match path.file_name() {
+    Some(f) => f,
+    None => return Err(format!("invalid staging directory: {:?}", path)),
+  };
+
+  return Ok(staging_path_abs(parent)?.join(file_name));
+}
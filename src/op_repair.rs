@@ -0,0 +1,140 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck repair [options] --output=<file> <path>
+Reconstruct a corrupted file from its recovery data
+
+The damaged blocks of the file are reconstructed from the recovery data that
+was computed by 'protect' and the repaired file is written to the output file.
+The file in the repository is never modified.
+
+options:
+  --output=FILE          Write the repaired file to FILE. The file must not
+                         exist
+  --snapshot=ID          Repair the file as recorded in the specified snapshot
+                         instead of the latest one
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  --colours=[on/off]     Turn coloured terminal output on or off
+                         default: on
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "output", "output", "FILE");
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optopt("", "colours", "progress", "ONOFF");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("repair", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+  ::prompt::set_colours(flags.opt_str("colours") != Some("off".to_owned()));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let output_path = match flags.opt_str("output") {
+    Some(p) => p,
+    None => return Err(::op::usage_error("need an output file (--output=FILE)".into())),
+  };
+
+  let path = match flags.free.len() {
+    1 => ::op::parse_repository_path(&flags.free[0]).map_err(::op::usage_error)?,
+    0 => return Err(::op::usage_error("need a path (e.g. 'integritycheck repair --output=/tmp/x photos/1.jpg')".into())),
+    _ => return Err(::op::usage_error("too many arguments".into())),
+  };
+
+  let path = match path.to_str() {
+    Some(p) => p.to_owned(),
+    None => return Err(::op::usage_error(format!("invalid path: {:?}", path))),
+  };
+
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+
+  let snapshot_ref = match flags.opt_str("snapshot") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots"))),
+    },
+  };
+
+  let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+
+  let checksum = match snapshot.get(&path).and_then(|f| f.checksum.to_owned()) {
+    Some(c) => c,
+    None => return Err(::op::usage_error(format!("file not found in snapshot: {:?}", path))),
+  };
+
+  let parity_file_path = index
+      .parity_path()
+      .join(format!("{}{}", checksum, ::parity::PARITY_FILE_SUFFIX));
+
+  let mut parity_data = Vec::<u8>::new();
+  if let Err(e) = fs::File::open(&parity_file_path).and_then(|mut f| f.read_to_end(&mut parity_data)) {
+    return Err(::op::index_error(format!(
+        "no recovery data for {:?}; maybe you need to run 'integritycheck protect' first? ({})",
+        path,
+        e)));
+  }
+
+  let parity = ::parity::ParityFile::decode(&parity_data).map_err(::op::index_error)?;
+
+  let file_path = Path::new(&data_path).join(&path);
+  let checksum_actual = ::checksum::compute_file(
+      snapshot.checksum_function.to_owned(),
+      &file_path).ok();
+
+  if checksum_actual == Some(checksum.to_owned()) {
+    ::prompt::print_success(&format!("{:?} is intact; nothing to repair", path));
+    return Ok(::op::Outcome::Clean);
+  }
+
+  let result = ::parity::repair(&file_path, &parity, &Path::new(&output_path))
+      .map_err(::op::unreadable_error)?;
+
+  ::prompt::print_repair_result(&path, &result);
+
+  if !result.repaired {
+    ::prompt::print_warning("too many damaged blocks; the file cannot be repaired");
+    return Ok(::op::Outcome::Corrupted);
+  }
+
+  let checksum_repaired = ::checksum::compute_file(
+      snapshot.checksum_function.to_owned(),
+      &Path::new(&output_path)).map_err(::op::unreadable_error)?;
+
+  if checksum_repaired != checksum {
+    ::prompt::print_warning(&format!(
+        "the repaired file {:?} does not match the snapshot",
+        output_path));
+    return Ok(::op::Outcome::Corrupted);
+  }
+
+  ::prompt::print_success(&format!("Wrote repaired file to {:?}", output_path));
+  return Ok(::op::Outcome::Clean);
+}
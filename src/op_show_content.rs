@@ -0,0 +1,90 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use getopts::Options;
+
+pub const USAGE : &'static str = "\
+usage: integritycheck show-content [options] <path>
+Print the stored contents of a file as of a snapshot
+
+The contents are only available if they were stored when the snapshot was
+created (see the --store_content option of 'init' and 'ack').
+
+options:
+  --snapshot=ID          Print the contents as of the specified snapshot instead
+                         of the latest one
+  -d,--data_dir=PATH     Set the path of the repository/data directory
+                         default: '.'
+  -x,--index_dir=PATH    Set the path of the index directory. Note that this
+                         path is relative to the data directory. Absolute
+                         paths are allowed. default: '.ic'
+  -v,--verbose           Enable verbose output,
+  -h,--help              Print this help message and exit
+";
+
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
+  let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "snapshot", "snapshot", "ID");
+  flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
+  flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
+  flag_cfg.optflag("v", "verbose", "verbose");
+
+  let flags = match ::config::parse_flags("show-content", &flag_cfg, args) {
+    Ok(f) => f,
+    Err(e) => return Err(::op::usage_error(e)),
+  };
+
+  ::prompt::set_debug(flags.opt_present("verbose"));
+
+  let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
+  let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+
+  let path = match flags.free.len() {
+    1 => ::op::parse_repository_path(&flags.free[0]).map_err(::op::usage_error)?,
+    0 => return Err(::op::usage_error("need a path (e.g. 'integritycheck show-content etc/fstab')".into())),
+    _ => return Err(::op::usage_error("too many arguments".into())),
+  };
+
+  let path = match path.to_str() {
+    Some(p) => p.to_owned(),
+    None => return Err(::op::usage_error(format!("invalid path: {:?}", path))),
+  };
+
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+
+  let snapshot_ref = match flags.opt_str("snapshot") {
+    Some(id) => index.find(&id).map_err(::op::usage_error)?,
+    None => match index.latest() {
+      Some(idx) => idx,
+      None => return Err(::op::index_error(format!("no snapshots"))),
+    },
+  };
+
+  let snapshot = index.load(&snapshot_ref).map_err(::op::index_error)?;
+
+  if snapshot.get(&path).is_none() {
+    return Err(::op::usage_error(format!("file not found in snapshot: {:?}", path)));
+  }
+
+  let data = match ::content_store::load_file(&index, &snapshot, &path) {
+    Ok(Some(data)) => data,
+    Ok(None) => return Err(::op::index_error(format!("the contents of {:?} were not stored", path))),
+    Err(e) => return Err(::op::index_error(e)),
+  };
+
+  if let Err(e) = io::stdout().write_all(&data) {
+    return Err(format!("error while writing to stdout: {}", e).into());
+  }
+
+  return Ok(::op::Outcome::Clean);
+}
@@ -7,6 +7,7 @@
  * use this file except in compliance with the License.
  */
 use std::path::{Path,PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use getopts::Options;
 
 pub const USAGE : &'static str = "\
@@ -14,6 +15,21 @@ usage: integritycheck status [options]
 Compare the current state of the repository to the latest snapshot
 
 options:
+  --format=FORMAT        Set the output format. Possible values:
+                         'text' (default), 'json', 'jsonl'
+  --log=TARGETS          Send a summary of this run and the changes found to the
+                         system log. Comma-separated list of 'syslog' and
+                         'journald', each optionally followed by ':SOCKET'
+  --syslog_enterprise_number=PEN
+                         Set the IANA private enterprise number that identifies
+                         the structured data of syslog messages. Required for
+                         the 'syslog' log target
+  --prometheus_file=PATH Write metrics about this run to the specified file in
+                         the Prometheus textfile collector format
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
   -d,--data_dir=PATH     Set the path of the repository/data directory
                          default: '.'
   -x,--index_dir=PATH    Set the path of the index directory. Note that this
@@ -25,19 +41,29 @@ options:
                          default: on
   -v,--verbose           Enable verbose output,
   -h,--help              Print this help message and exit
+
+Notifications about the changes found and lists of known-bad checksums can be
+configured in the file 'config' in the index directory. See the README for
+details.
 ";
 
-pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
   let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "format", "format", "FORMAT");
+  flag_cfg.optopt("", "log", "log", "TARGETS");
+  flag_cfg.optopt("", "syslog_enterprise_number", "syslog_enterprise_number", "PEN");
+  flag_cfg.optopt("", "prometheus_file", "prometheus_file", "PATH");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
   flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
   flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
   flag_cfg.optopt("", "progress", "progress", "ONOFF");
   flag_cfg.optopt("", "colours", "progress", "ONOFF");
   flag_cfg.optflag("v", "verbose", "verbose");
 
-  let flags = match flag_cfg.parse(args) {
+  let flags = match ::config::parse_flags("status", &flag_cfg, args) {
     Ok(f) => f,
-    Err(e) => return Err(e.to_string()),
+    Err(e) => return Err(::op::usage_error(e)),
   };
 
   ::prompt::set_debug(flags.opt_present("verbose"));
@@ -46,46 +72,128 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
 
   let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
   let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+  let format = ::prompt::parse_output_format(flags.opt_str("format"))
+      .map_err(::op::usage_error)?;
+  let log_targets = ::logging::parse_targets(
+      &flags.opt_str("log").unwrap_or(String::new()),
+      flags.opt_str("syslog_enterprise_number")).map_err(::op::usage_error)?;
 
   ::prompt::print_progress_step(1, 4, "Loading index");
-  let index = ::IndexDirectory::open(&Path::new(&data_path), &Path::new(&index_path))?;
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+  let config = ::config::Config::load(&index.config_path())?;
   let snapshot_target_ref = index.latest();
   let snapshot_target = match &snapshot_target_ref {
-    &Some(ref idx) => index.load(&idx)?,
-    &None => return Err(format!("no snapshots"))
+    &Some(ref idx) => index.load(&idx).map_err(::op::index_error)?,
+    &None => return Err(::op::index_error(format!("no snapshots")))
+  };
+
+  let denylist = ::denylist::Denylist::load(
+      &config,
+      index.path(),
+      &snapshot_target.checksum_function)?;
+
+  let scan_opts = ::index_scan::ScanOptions {
+    exclude_paths: vec!(PathBuf::from(&index_path)).into(),
+    exclusive_paths: None,
+    ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+        &flags.opt_strs("exclude"),
+        &flags.opt_strs("include")).map_err(::op::usage_error)?,
   };
 
   ::prompt::print_progress_step(2, 4, "Scanning file metadata");
+  let scan_start = Instant::now();
   let mut snapshot_actual = ::index_scan::scan_metadata(
       &Path::new(&data_path),
       ::IndexSnapshot::new(snapshot_target.checksum_function.to_owned()),
-      &::index_scan::ScanOptions {
-        exclude_paths: vec!(PathBuf::from(&index_path)),
-        exclusive_paths: None,
-      })?;
+      &scan_opts).map_err(::op::unreadable_error)?;
 
   ::prompt::print_progress_step(3, 4, "Computing file checksums for changed files");
   snapshot_actual = ::index_scan::copy_checksums(
       snapshot_actual.to_owned(),
       &snapshot_target)?;
 
+  let scan_bytes = ::index_scan::pending_size_bytes(&snapshot_actual);
   snapshot_actual = ::index_scan::scan_checksums(
       &Path::new(&data_path),
       snapshot_actual.to_owned(),
-      &::index_scan::ScanOptions {
-        exclude_paths: vec!(PathBuf::from(&index_path)),
-        exclusive_paths: None
-      })?;
+      &scan_opts).map_err(::op::unreadable_error)?;
+
+  let scan_duration = scan_start.elapsed();
 
   ::prompt::print_progress_step(4, 4, "Computing diff");
   let diff = ::index_diff::diff(&snapshot_target, &snapshot_actual);
+  let known_bad = denylist.check(&snapshot_actual);
 
   ::prompt::print_progress_complete();
+
+  let snapshot_target_ref = snapshot_target_ref.unwrap();
+  ::notify::notify(
+      &config,
+      "status",
+      &data_path,
+      &snapshot_target_ref,
+      &snapshot_target,
+      &diff,
+      &known_bad)?;
+
+  ::logging::log_run(
+      &log_targets,
+      "status",
+      &data_path,
+      &snapshot_target_ref,
+      &diff,
+      &known_bad);
+
+  if let Some(metrics_path) = flags.opt_str("prometheus_file") {
+    let time = match SystemTime::now().duration_since(UNIX_EPOCH) {
+      Ok(v) => v.as_secs() as i64 * 1_000_000 + v.subsec_nanos() as i64 / 1_000,
+      Err(e) => return Err(format!("internal error: {}", e).into()),
+    };
+
+    ::metrics::write_prometheus_file(
+        &Path::new(&metrics_path),
+        "status",
+        &data_path,
+        &snapshot_target_ref,
+        &snapshot_target,
+        &diff,
+        &known_bad,
+        &::metrics::ScanStats {
+          duration_us:
+              scan_duration.as_secs() as i64 * 1_000_000 +
+              scan_duration.subsec_nanos() as i64 / 1_000,
+          bytes_scanned: scan_bytes,
+          oldest_verification_us: None,
+        },
+        time)?;
+  }
+
+  let outcome = ::op::outcome_from_status(&diff, &known_bad);
+
+  if format != ::prompt::OutputFormat::Text {
+    let mut summary = ::json::status_fields(
+        "status",
+        &data_path,
+        &snapshot_target_ref,
+        &snapshot_target,
+        &diff);
+
+    if !denylist.is_empty() {
+      summary.push(("known_bad", ::json::encode_known_bad(&known_bad)));
+    }
+
+    ::prompt::print_status_json(&format, summary, &diff);
+    return Ok(outcome);
+  }
+
   ::prompt::print_repository_path(&data_path);
   ::prompt::print_repository_size(&snapshot_target);
-  ::prompt::print_snapshot_time(snapshot_target_ref.unwrap().timestamp_us);
-  ::prompt::print_repository_status(diff.len() == 0);
+  ::prompt::print_snapshot_time(snapshot_target_ref.timestamp_us);
+  ::prompt::print_repository_status(outcome == ::op::Outcome::Clean);
   ::prompt::print_diff(&diff);
+  ::prompt::print_known_bad(&known_bad);
 
-  return Ok(diff.len() == 0);
+  return Ok(outcome);
 }
@@ -7,13 +7,43 @@
  * use this file except in compliance with the License.
  */
 use std::path::{Path,PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use getopts::Options;
 
 pub const USAGE : &'static str = "\
 usage: integritycheck verify [options]
 Perform a full check of the repository's integrity
 
+A full check saves its progress to a checkpoint in the index directory at
+regular intervals. If it is interrupted, run it again with --resume to continue
+where it stopped; only files whose metadata changed since then are read again.
+
+With --budget or --fraction, only the least recently verified files are read,
+so that a large repository is fully verified over a series of runs. Created,
+deleted and modified files are still detected in every run.
+
 options:
+  --budget=DURATION      Stop verifying files once the time budget is used up,
+                         e.g. '30m' or '4h'
+  --fraction=PERCENT     Only verify the given percentage of the files, e.g. '5%'
+  --resume               Continue an interrupted check from its last checkpoint
+  --checkpoint_interval=DURATION
+                         Set the time between two checkpoints. default: '1m'
+  --format=FORMAT        Set the output format. Possible values:
+                         'text' (default), 'json', 'jsonl'
+  --log=TARGETS          Send a summary of this run and the changes found to the
+                         system log. Comma-separated list of 'syslog' and
+                         'journald', each optionally followed by ':SOCKET'
+  --syslog_enterprise_number=PEN
+                         Set the IANA private enterprise number that identifies
+                         the structured data of syslog messages. Required for
+                         the 'syslog' log target
+  --prometheus_file=PATH Write metrics about this run to the specified file in
+                         the Prometheus textfile collector format
+  --exclude=PATTERN      Exclude files matching a gitignore-style pattern. May
+                         be given multiple times
+  --include=PATTERN      Include files matching a gitignore-style pattern even
+                         if they are excluded by --exclude or an .icignore file
   -d,--data_dir=PATH     Set the path of the repository/data directory
                          default: '.'
   -x,--index_dir=PATH    Set the path of the index directory. Note that this
@@ -25,19 +55,33 @@ options:
                          default: on
   -v,--verbose           Enable verbose output,
   -h,--help              Print this help message and exit
+
+Notifications about the changes found and lists of known-bad checksums can be
+configured in the file 'config' in the index directory. See the README for
+details.
 ";
 
-pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
+pub fn perform(args: &Vec<String>) -> ::op::OperationResult {
   let mut flag_cfg = Options::new();
+  flag_cfg.optopt("", "budget", "budget", "DURATION");
+  flag_cfg.optopt("", "fraction", "fraction", "PERCENT");
+  flag_cfg.optflag("", "resume", "resume");
+  flag_cfg.optopt("", "checkpoint_interval", "checkpoint_interval", "DURATION");
+  flag_cfg.optopt("", "format", "format", "FORMAT");
+  flag_cfg.optopt("", "log", "log", "TARGETS");
+  flag_cfg.optopt("", "syslog_enterprise_number", "syslog_enterprise_number", "PEN");
+  flag_cfg.optopt("", "prometheus_file", "prometheus_file", "PATH");
+  flag_cfg.optmulti("", "exclude", "exclude", "PATTERN");
+  flag_cfg.optmulti("", "include", "include", "PATTERN");
   flag_cfg.optopt("d", "data_dir", "data_dir", "PATH");
   flag_cfg.optopt("x", "index_dir", "index_dir", "PATH");
   flag_cfg.optopt("", "progress", "progress", "ONOFF");
   flag_cfg.optopt("", "colours", "progress", "ONOFF");
   flag_cfg.optflag("v", "verbose", "verbose");
 
-  let flags = match flag_cfg.parse(args) {
+  let flags = match ::config::parse_flags("verify", &flag_cfg, args) {
     Ok(f) => f,
-    Err(e) => return Err(e.to_string()),
+    Err(e) => return Err(::op::usage_error(e)),
   };
 
   ::prompt::set_debug(flags.opt_present("verbose"));
@@ -46,41 +90,254 @@ pub fn perform(args: &Vec<String>) -> Result<bool, ::Error> {
 
   let data_path = flags.opt_str("data_dir").unwrap_or(::DEFAULT_DATA_DIR.into());
   let index_path = flags.opt_str("index_dir").unwrap_or(::DEFAULT_INDEX_DIR.into());
+  let format = ::prompt::parse_output_format(flags.opt_str("format"))
+      .map_err(::op::usage_error)?;
+  let log_targets = ::logging::parse_targets(
+      &flags.opt_str("log").unwrap_or(String::new()),
+      flags.opt_str("syslog_enterprise_number")).map_err(::op::usage_error)?;
+
+  let budget = match flags.opt_str("budget") {
+    Some(v) => Some(Duration::from_secs(::prompt::parse_duration(&v).map_err(::op::usage_error)?)),
+    None => None,
+  };
+
+  let fraction = match flags.opt_str("fraction") {
+    Some(v) => match v.trim_end_matches('%').parse::<f64>() {
+      Ok(v) if v > 0.0 && v <= 100.0 => Some(v / 100.0),
+      _ => return Err(::op::usage_error(format!("invalid fraction: {}", v))),
+    },
+    None => None,
+  };
+
+  let sampled = budget.is_some() || fraction.is_some();
+  let resume = flags.opt_present("resume");
+  if resume && sampled {
+    return Err(::op::usage_error("--resume can not be combined with --budget or --fraction".into()));
+  }
+
+  let checkpoint_interval = Duration::from_secs(match flags.opt_str("checkpoint_interval") {
+    Some(v) => ::prompt::parse_duration(&v).map_err(::op::usage_error)?,
+    None => ::verify_state::DEFAULT_CHECKPOINT_INTERVAL_SECS,
+  });
 
   ::prompt::print_progress_step(1, 4, "Loading index");
-  let index = ::IndexDirectory::open(&Path::new(&data_path), &Path::new(&index_path))?;
+  let index = ::IndexDirectory::open(
+      &Path::new(&data_path),
+      &Path::new(&index_path)).map_err(::op::index_error)?;
+  let config = ::config::Config::load(&index.config_path())?;
+  let mut verify_state = ::verify_state::VerifyState::load(&index).map_err(::op::index_error)?;
   let snapshot_target_ref = index.latest();
   let snapshot_target = match &snapshot_target_ref {
-    &Some(ref idx) => index.load(&idx)?,
-    &None => return Err(format!("no snapshots"))
+    &Some(ref idx) => index.load(&idx).map_err(::op::index_error)?,
+    &None => return Err(::op::index_error(format!("no snapshots")))
   };
 
+  let denylist = ::denylist::Denylist::load(
+      &config,
+      index.path(),
+      &snapshot_target.checksum_function)?;
+
+  let snapshot_target_ref = snapshot_target_ref.unwrap();
+
   ::prompt::print_progress_step(2, 4, "Scanning file metadata");
+  let scan_start = Instant::now();
   let scan_opts = ::index_scan::ScanOptions {
-    exclude_paths: vec!(PathBuf::from(&index_path)),
+    exclude_paths: vec!(PathBuf::from(&index_path)).into(),
     exclusive_paths: None,
+    ignore_rules: ::index_ignore::IgnoreRules::from_patterns(
+        &flags.opt_strs("exclude"),
+        &flags.opt_strs("include")).map_err(::op::usage_error)?,
   };
 
   let mut snapshot_actual = ::index_scan::scan_metadata(
       &Path::new(&data_path),
       ::IndexSnapshot::new(snapshot_target.checksum_function.to_owned()),
-      &scan_opts)?;
+      &scan_opts).map_err(::op::unreadable_error)?;
 
   ::prompt::print_progress_step(3, 4, "Computing file checksums");
-  snapshot_actual = ::index_scan::scan_checksums(
-      &Path::new(&data_path),
-      snapshot_actual,
-      &scan_opts)?;
+  let files_verified;
+  let mut scan_bytes;
+  if sampled {
+    /* files with unchanged metadata are taken from the snapshot unless sampled */
+    snapshot_actual = ::index_scan::copy_checksums(
+        snapshot_actual.to_owned(),
+        &snapshot_target)?;
+
+    let mut sample : Vec<String> = verify_state
+        .order(&snapshot_target)
+        .into_iter()
+        .filter(|p| snapshot_actual.get(p).map(|f| f.checksum.is_some()).unwrap_or(false))
+        .collect();
+
+    if let Some(fraction) = fraction {
+      sample.truncate((snapshot_target.total_file_count() as f64 * fraction).ceil() as usize);
+    }
+
+    scan_bytes = ::index_scan::pending_size_bytes(&snapshot_actual);
+    snapshot_actual = ::index_scan::scan_checksums(
+        &Path::new(&data_path),
+        snapshot_actual,
+        &scan_opts).map_err(::op::unreadable_error)?;
+
+    let (snapshot_rescanned, sample_scanned) = ::index_scan::rescan_checksums(
+        &Path::new(&data_path),
+        snapshot_actual,
+        &sample,
+        budget.map(|b| scan_start + b)).map_err(::op::unreadable_error)?;
+
+    snapshot_actual = snapshot_rescanned;
+    scan_bytes += sample_scanned
+        .iter()
+        .filter_map(|p| snapshot_actual.get(p))
+        .fold(0, |acc, finfo| acc + finfo.size_bytes);
+
+    files_verified = sample_scanned;
+  } else {
+    if resume {
+      match ::verify_state::load_checkpoint(&index, &snapshot_target_ref) {
+        Ok(Some(checkpoint)) => {
+          snapshot_actual = ::index_scan::copy_checksums(snapshot_actual, &checkpoint)?;
+        },
+        Ok(None) => ::prompt::print_warning("no checkpoint found; verifying all files"),
+        Err(e) => ::prompt::print_warning(&format!("ignoring checkpoint: {}", e)),
+      };
+    }
+
+    /* a read-only index directory must not fail the check */
+    let mut checkpoint_failed = false;
+    scan_bytes = ::index_scan::pending_size_bytes(&snapshot_actual);
+    snapshot_actual = ::index_scan::scan_checksums_checkpointed(
+        &Path::new(&data_path),
+        snapshot_actual,
+        &scan_opts,
+        Some(checkpoint_interval),
+        |snapshot| {
+          if checkpoint_failed {
+            return Ok(());
+          }
+
+          if let Err(e) = ::verify_state::save_checkpoint(&index, &snapshot_target_ref, snapshot) {
+            ::prompt::print_warning(&format!("failed to save checkpoint: {}", e));
+            checkpoint_failed = true;
+          }
+
+          return Ok(());
+        })
+        .map_err(::op::unreadable_error)?;
+
+    if let Err(e) = ::verify_state::remove_checkpoint(&index) {
+      ::prompt::print_warning(&e);
+    }
+    files_verified = snapshot_target.list();
+  }
+
+  let scan_duration = scan_start.elapsed();
 
   ::prompt::print_progress_step(4, 4, "Computing diff");
   let diff = ::index_diff::diff(&snapshot_target, &snapshot_actual);
+  let known_bad = denylist.check(&snapshot_actual);
 
   ::prompt::print_progress_complete();
+
+  let time = match SystemTime::now().duration_since(UNIX_EPOCH) {
+    Ok(v) => v.as_secs() as i64 * 1_000_000 + v.subsec_nanos() as i64 / 1_000,
+    Err(e) => return Err(format!("internal error: {}", e).into()),
+  };
+
+  /* only files that match the snapshot count as verified */
+  for fpath in &files_verified {
+    let checksum_actual = snapshot_actual.get(fpath).and_then(|f| f.checksum.as_ref());
+    let checksum_target = snapshot_target.get(fpath).and_then(|f| f.checksum.as_ref());
+    if let (Some(c1), Some(c2)) = (checksum_actual, checksum_target) {
+      if c1 == c2 {
+        verify_state.set_verified(fpath, c1, time);
+      }
+    }
+  }
+
+  if let Err(e) = verify_state.save(&index, &snapshot_target) {
+    ::prompt::print_warning(&e);
+  }
+
+  let oldest_verification = verify_state.oldest(
+      &snapshot_target,
+      snapshot_target_ref.timestamp_us);
+
+  ::notify::notify(
+      &config,
+      "verify",
+      &data_path,
+      &snapshot_target_ref,
+      &snapshot_target,
+      &diff,
+      &known_bad)?;
+
+  ::logging::log_run(
+      &log_targets,
+      "verify",
+      &data_path,
+      &snapshot_target_ref,
+      &diff,
+      &known_bad);
+
+  if let Some(metrics_path) = flags.opt_str("prometheus_file") {
+    ::metrics::write_prometheus_file(
+        &Path::new(&metrics_path),
+        "verify",
+        &data_path,
+        &snapshot_target_ref,
+        &snapshot_target,
+        &diff,
+        &known_bad,
+        &::metrics::ScanStats {
+          duration_us:
+              scan_duration.as_secs() as i64 * 1_000_000 +
+              scan_duration.subsec_nanos() as i64 / 1_000,
+          bytes_scanned: scan_bytes,
+          oldest_verification_us: oldest_verification,
+        },
+        time)?;
+  }
+
+  let outcome = ::op::outcome_from_status(&diff, &known_bad);
+
+  if format != ::prompt::OutputFormat::Text {
+    let mut summary = ::json::status_fields(
+        "verify",
+        &data_path,
+        &snapshot_target_ref,
+        &snapshot_target,
+        &diff);
+
+    summary.push(("files_verified", files_verified.len().to_string()));
+    summary.push((
+        "oldest_verified",
+        oldest_verification.map(|t| t.to_string()).unwrap_or("null".to_owned())));
+
+    if !denylist.is_empty() {
+      summary.push(("known_bad", ::json::encode_known_bad(&known_bad)));
+    }
+
+    ::prompt::print_status_json(&format, summary, &diff);
+    return Ok(outcome);
+  }
+
   ::prompt::print_repository_path(&data_path);
   ::prompt::print_repository_size(&snapshot_target);
-  ::prompt::print_snapshot_time(snapshot_target_ref.unwrap().timestamp_us);
-  ::prompt::print_repository_status(diff.len() == 0);
+  ::prompt::print_snapshot_time(snapshot_target_ref.timestamp_us);
+  if let Some(timestamp_us) = oldest_verification {
+    ::prompt::print_verification_time(timestamp_us);
+  }
+
+  if sampled {
+    ::prompt::print_verification_sample(
+        files_verified.len() as u64,
+        snapshot_target.total_file_count());
+  }
+
+  ::prompt::print_repository_status(outcome == ::op::Outcome::Clean);
   ::prompt::print_diff(&diff);
+  ::prompt::print_known_bad(&known_bad);
 
-  return Ok(diff.len() == 0);
+  return Ok(outcome);
 }
@@ -0,0 +1,445 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::cmp;
+use std::fs::{File,OpenOptions};
+use std::io::{Read,Seek,SeekFrom,Write};
+use std::path::Path;
+
+pub const PARITY_DIRNAME : &'static str = "parity";
+pub const PARITY_FILE_SUFFIX : &'static str = ".par";
+pub const MAX_DATA_BLOCKS : u64 = 128;
+pub const DEFAULT_BLOCK_SIZE : u64 = 4096;
+pub const DEFAULT_REDUNDANCY : u64 = 10;
+
+const PARITY_FILE_MAGIC : &'static str = "#icparity 1";
+const FIELD_POLYNOMIAL : u16 = 0x11d;
+
+/**
+ * Recovery data for a single file. The file is split into up to 128 data blocks
+ * and the parity blocks are computed with a systematic Reed-Solomon code over
+ * GF(2^8) using a Cauchy matrix, so that any combination of damaged data blocks
+ * can be reconstructed as long as at least as many parity blocks are intact.
+ * The MD5 checksum of every block is stored to find the damaged blocks.
+ *
+ * The file format consists of a text header followed by the raw parity blocks:
+ *
+ *   #icparity 1
+ *   size <bytes>
+ *   block_size <bytes>
+ *   data_blocks <n>
+ *   parity_blocks <m>
+ *   block <md5>          (n + m lines, data blocks first)
+ *   <empty line>
+ *   <m * block_size bytes of parity data>
+ */
+#[derive(Clone, Debug)]
+pub struct ParityFile {
+  pub size_bytes: u64,
+  pub block_size: u64,
+  pub data_checksums: Vec<String>,
+  pub parity_checksums: Vec<String>,
+  pub parity: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RepairResult {
+  pub data_blocks: usize,
+  pub damaged_blocks: usize,
+  pub parity_blocks: usize,
+  pub parity_blocks_intact: usize,
+  pub repaired: bool,
+}
+
+struct Field {
+  exp: [u8; 512],
+  log: [u8; 256],
+}
+
+impl Field {
+
+  fn new() -> Field {
+    let mut field = Field {
+      exp: [0; 512],
+      log: [0; 256],
+    };
+
+    let mut x : u16 = 1;
+    for i in 0..255 {
+      field.exp[i] = x as u8;
+      field.log[x as usize] = i as u8;
+      x <<= 1;
+      if x & 0x100 != 0 {
+        x ^= FIELD_POLYNOMIAL;
+      }
+    }
+
+    for i in 255..512 {
+      field.exp[i] = field.exp[i - 255];
+    }
+
+    return field;
+  }
+
+  fn mul(self: &Self, a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+      return 0;
+    }
+
+    return self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize];
+  }
+
+  fn inv(self: &Self, a: u8) -> u8 {
+    return self.exp[255 - self.log[a as usize] as usize];
+  }
+
+  fn mul_table(self: &Self, c: u8) -> Vec<u8> {
+    return (0..256).map(|v| self.mul(c, v as u8)).collect();
+  }
+
+  /**
+   * The coefficient of data block 'i' in parity block 'j'. Data block i maps to
+   * the field element i and parity block j to the element data_blocks + j
+   */
+  fn cauchy(self: &Self, data_blocks: usize, j: usize, i: usize) -> u8 {
+    return self.inv(((data_blocks + j) as u8) ^ (i as u8));
+  }
+
+  fn invert(self: &Self, matrix: &Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, ::Error> {
+    let n = matrix.len();
+    let mut a = matrix.to_owned();
+    let mut b : Vec<Vec<u8>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1 } else { 0 }).collect())
+        .collect();
+
+    for col in 0..n {
+      let pivot = match (col..n).find(|&r| a[r][col] != 0) {
+        Some(r) => r,
+        None => return Err("internal error: singular recovery matrix".into()),
+      };
+
+      a.swap(col, pivot);
+      b.swap(col, pivot);
+
+      let pivot_inv = self.inv(a[col][col]);
+      for k in 0..n {
+        a[col][k] = self.mul(a[col][k], pivot_inv);
+        b[col][k] = self.mul(b[col][k], pivot_inv);
+      }
+
+      for row in 0..n {
+        let factor = a[row][col];
+        if row == col || factor == 0 {
+          continue;
+        }
+
+        for k in 0..n {
+          a[row][k] ^= self.mul(factor, a[col][k]);
+          b[row][k] ^= self.mul(factor, b[col][k]);
+        }
+      }
+    }
+
+    return Ok(b);
+  }
+
+}
+
+impl ParityFile {
+
+  pub fn encode(self: &Self) -> Vec<u8> {
+    let mut header = String::new();
+    header += &format!("{}\n", PARITY_FILE_MAGIC);
+    header += &format!("size {}\n", self.size_bytes);
+    header += &format!("block_size {}\n", self.block_size);
+    header += &format!("data_blocks {}\n", self.data_checksums.len());
+    header += &format!("parity_blocks {}\n", self.parity_checksums.len());
+
+    for checksum in self.data_checksums.iter().chain(self.parity_checksums.iter()) {
+      header += &format!("block {}\n", checksum);
+    }
+
+    header += "\n";
+
+    let mut data = header.into_bytes();
+    for block in &self.parity {
+      data.extend_from_slice(block);
+    }
+
+    return data;
+  }
+
+  pub fn decode(data: &[u8]) -> Result<ParityFile, ::Error> {
+    let header_len = match data.windows(2).position(|w| w == b"\n\n") {
+      Some(p) => p + 2,
+      None => return Err("invalid parity file: missing header".into()),
+    };
+
+    let header = match ::std::str::from_utf8(&data[..header_len]) {
+      Ok(h) => h,
+      Err(_) => return Err("invalid parity file: invalid header".into()),
+    };
+
+    let mut lines = header.lines();
+    if lines.next() != Some(PARITY_FILE_MAGIC) {
+      return Err("invalid parity file: unsupported version".into());
+    }
+
+    let mut size_bytes = None;
+    let mut block_size = None;
+    let mut data_blocks = None;
+    let mut parity_blocks = None;
+    let mut checksums = Vec::<String>::new();
+
+    for line in lines.filter(|l| !l.is_empty()) {
+      let mut fields = line.splitn(2, ' ');
+      let key = fields.next().unwrap_or("");
+      let value = fields.next().unwrap_or("");
+      let parse = || value.parse::<u64>().map_err(|_| format!("invalid parity file: {}", line));
+
+      match key {
+        "size" => size_bytes = Some(parse()?),
+        "block_size" => block_size = Some(parse()?),
+        "data_blocks" => data_blocks = Some(parse()? as usize),
+        "parity_blocks" => parity_blocks = Some(parse()? as usize),
+        "block" => checksums.push(value.to_owned()),
+        _ => return Err(format!("invalid parity file: {}", line)),
+      };
+    }
+
+    let (size_bytes, block_size, data_blocks, parity_blocks) =
+        match (size_bytes, block_size, data_blocks, parity_blocks) {
+          (Some(s), Some(b), Some(n), Some(m)) => (s, b, n, m),
+          _ => return Err("invalid parity file: incomplete header".into()),
+        };
+
+    if block_size == 0 ||
+       data_blocks + parity_blocks > 256 ||
+       (size_bytes + block_size - 1) / block_size != data_blocks as u64 ||
+       checksums.len() != data_blocks + parity_blocks ||
+       data.len() as u64 != header_len as u64 + parity_blocks as u64 * block_size {
+      return Err("invalid parity file: inconsistent header".into());
+    }
+
+    let parity = data[header_len..]
+        .chunks(block_size as usize)
+        .map(|c| c.to_vec())
+        .collect();
+
+    let parity_checksums = checksums.split_off(data_blocks);
+
+    return Ok(ParityFile {
+      size_bytes: size_bytes,
+      block_size: block_size,
+      data_checksums: checksums,
+      parity_checksums: parity_checksums,
+      parity: parity,
+    });
+  }
+
+}
+
+/**
+ * Compute the recovery data for a file. The block size is at least
+ * 'min_block_size' and large enough to split the file into at most
+ * MAX_DATA_BLOCKS blocks; 'redundancy' is the number of parity blocks in percent
+ * of the number of data blocks
+ */
+pub fn compute(
+    path: &Path,
+    min_block_size: u64,
+    redundancy: u64) -> Result<ParityFile, ::Error> {
+  let size_bytes = match path.metadata() {
+    Ok(m) => m.len(),
+    Err(e) => return Err(e.to_string()),
+  };
+
+  let block_size = cmp::max(
+      min_block_size,
+      (size_bytes + MAX_DATA_BLOCKS - 1) / MAX_DATA_BLOCKS);
+  let data_blocks = ((size_bytes + block_size - 1) / block_size) as usize;
+  let parity_blocks = match data_blocks {
+    0 => 0,
+    n => cmp::max(1, (n as u64 * redundancy + 99) / 100) as usize,
+  };
+
+  let field = Field::new();
+  let mut file = File::open(path).map_err(|e| e.to_string())?;
+  let mut parity = vec!(vec!(0u8; block_size as usize); parity_blocks);
+  let mut data_checksums = Vec::<String>::new();
+  let mut block = vec!(0u8; block_size as usize);
+
+  for i in 0..data_blocks {
+    let len = block_len(size_bytes, block_size, i);
+    if let Err(e) = read_block(Some(&mut file), i as u64 * block_size, &mut block, len) {
+      return Err(format!("error while reading {:?}: {}", path, e));
+    }
+
+    data_checksums.push(block_checksum(&block));
+
+    for j in 0..parity_blocks {
+      let table = field.mul_table(field.cauchy(data_blocks, j, i));
+      for (p, d) in parity[j].iter_mut().zip(block.iter()) {
+        *p ^= table[*d as usize];
+      }
+    }
+  }
+
+  return Ok(ParityFile {
+    size_bytes: size_bytes,
+    block_size: block_size,
+    data_checksums: data_checksums,
+    parity_checksums: parity.iter().map(|b| block_checksum(b)).collect(),
+    parity: parity,
+  });
+}
+
+/**
+ * Reconstruct the damaged blocks of a file and write the repaired file to
+ * 'output'. The output file is only written if all damaged blocks could be
+ * reconstructed. A missing or truncated input file is treated as damaged
+ */
+pub fn repair(
+    path: &Path,
+    parity_file: &ParityFile,
+    output: &Path) -> Result<RepairResult, ::Error> {
+  let field = Field::new();
+  let data_blocks = parity_file.data_checksums.len();
+  let block_size = parity_file.block_size as usize;
+  let mut block = vec!(0u8; block_size);
+
+  /* find the damaged data blocks and sum up the intact ones for each parity
+     block. The data blocks that are missing from the sum are then solved for */
+  let mut file = File::open(path).ok();
+  let mut damaged = Vec::<usize>::new();
+  let mut syndromes = parity_file.parity.to_owned();
+
+  for i in 0..data_blocks {
+    let len = block_len(parity_file.size_bytes, parity_file.block_size, i);
+    let offset = i as u64 * parity_file.block_size;
+    let intact = read_block(file.as_mut(), offset, &mut block, len).is_ok() &&
+        block_checksum(&block) == parity_file.data_checksums[i];
+
+    if !intact {
+      damaged.push(i);
+      continue;
+    }
+
+    for (j, syndrome) in syndromes.iter_mut().enumerate() {
+      let table = field.mul_table(field.cauchy(data_blocks, j, i));
+      for (s, d) in syndrome.iter_mut().zip(block.iter()) {
+        *s ^= table[*d as usize];
+      }
+    }
+  }
+
+  let parity_intact : Vec<usize> = (0..parity_file.parity.len())
+      .filter(|&j| block_checksum(&parity_file.parity[j]) == parity_file.parity_checksums[j])
+      .collect();
+
+  let mut result = RepairResult {
+    data_blocks: data_blocks,
+    damaged_blocks: damaged.len(),
+    parity_blocks: parity_file.parity.len(),
+    parity_blocks_intact: parity_intact.len(),
+    repaired: false,
+  };
+
+  if damaged.len() > parity_intact.len() {
+    return Ok(result);
+  }
+
+  /* solve the linear system formed by the first intact parity blocks */
+  let rows : Vec<usize> = parity_intact.into_iter().take(damaged.len()).collect();
+  let matrix = rows
+      .iter()
+      .map(|&j| damaged.iter().map(|&i| field.cauchy(data_blocks, j, i)).collect())
+      .collect();
+
+  let matrix_inv = field.invert(&matrix)?;
+  let mut reconstructed = vec!(vec!(0u8; block_size); damaged.len());
+  for (k, target) in reconstructed.iter_mut().enumerate() {
+    for (r, &j) in rows.iter().enumerate() {
+      let table = field.mul_table(matrix_inv[k][r]);
+      for (t, s) in target.iter_mut().zip(syndromes[j].iter()) {
+        *t ^= table[*s as usize];
+      }
+    }
+  }
+
+  /* write the repaired file */
+  let mut output_file = match OpenOptions::new().write(true).create_new(true).open(output) {
+    Ok(f) => f,
+    Err(e) => return Err(format!("error while creating {:?}: {}", output, e)),
+  };
+
+  let mut file = File::open(path).ok();
+  for i in 0..data_blocks {
+    let len = block_len(parity_file.size_bytes, parity_file.block_size, i);
+    let data = match damaged.iter().position(|&d| d == i) {
+      Some(k) => &reconstructed[k][..len],
+      None => {
+        let offset = i as u64 * parity_file.block_size;
+        if let Err(e) = read_block(file.as_mut(), offset, &mut block, len) {
+          return Err(format!("error while reading {:?}: {}", path, e));
+        }
+
+        &block[..len]
+      }
+    };
+
+    if let Err(e) = output_file.write_all(data) {
+      return Err(format!("error while writing {:?}: {}", output, e));
+    }
+  }
+
+  result.repaired = true;
+  return Ok(result);
+}
+
+fn block_len(size_bytes: u64, block_size: u64, i: usize) -> usize {
+  return cmp::min(block_size, size_bytes - i as u64 * block_size) as usize;
+}
+
+/**
+ * Read 'len' bytes at 'offset' into the block and pad it with zeros. Returns an
+ * error if the file is missing, unreadable or shorter than expected
+ */
+fn read_block(
+    file: Option<&mut File>,
+    offset: u64,
+    block: &mut Vec<u8>,
+    len: usize) -> Result<(), ::Error> {
+  for b in block.iter_mut() {
+    *b = 0;
+  }
+
+  let file = match file {
+    Some(f) => f,
+    None => return Err("file not found".into()),
+  };
+
+  if let Err(e) = file.seek(SeekFrom::Start(offset)) {
+    return Err(e.to_string());
+  }
+
+  let mut pos = 0;
+  while pos < len {
+    match file.read(&mut block[pos..len]) {
+      Ok(0) => return Err("unexpected end of file".into()),
+      Ok(n) => pos += n,
+      Err(e) => return Err(e.to_string()),
+    };
+  }
+
+  return Ok(());
+}
+
+fn block_checksum(block: &[u8]) -> String {
+  return ::checksum::compute(::checksum::ChecksumFunction::MD5, block);
+}
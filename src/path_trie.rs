@@ -0,0 +1,94 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path,PathBuf};
+
+/**
+ * A set of path prefixes stored as a trie of path components. Lookups take
+ * time proportional to the depth of the looked up path instead of the number
+ * of paths in the set.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct PathTrie {
+  children: HashMap<OsString, PathTrie>,
+  terminal: bool,
+}
+
+impl PathTrie {
+
+  pub fn new() -> PathTrie {
+    return PathTrie::default();
+  }
+
+  pub fn insert(self: &mut Self, path: &Path) {
+    let mut node = self;
+    for component in path.components() {
+      node = node
+          .children
+          .entry(component.as_os_str().to_owned())
+          .or_insert(PathTrie::default());
+    }
+
+    node.terminal = true;
+  }
+
+  /**
+   * Returns true if the path or one of its parents is in the set
+   */
+  pub fn contains_prefix_of(self: &Self, path: &Path) -> bool {
+    let mut node = self;
+    for component in path.components() {
+      if node.terminal {
+        return true;
+      }
+
+      node = match node.children.get(component.as_os_str()) {
+        Some(n) => n,
+        None => return false,
+      };
+    }
+
+    return node.terminal;
+  }
+
+  /**
+   * Returns true if the path, one of its parents or one of its children is in
+   * the set, i.e. if a directory at this path may contain matching files
+   */
+  pub fn intersects(self: &Self, path: &Path) -> bool {
+    let mut node = self;
+    for component in path.components() {
+      if node.terminal {
+        return true;
+      }
+
+      node = match node.children.get(component.as_os_str()) {
+        Some(n) => n,
+        None => return false,
+      };
+    }
+
+    return true;
+  }
+
+}
+
+impl From<Vec<PathBuf>> for PathTrie {
+
+  fn from(paths: Vec<PathBuf>) -> PathTrie {
+    let mut trie = PathTrie::new();
+    for path in paths {
+      trie.insert(&path);
+    }
+
+    return trie;
+  }
+
+}
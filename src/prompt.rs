@@ -13,6 +13,13 @@ use colored::*;
 use libc;
 use time;
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+  Text,
+  JSON,
+  JSONLines,
+}
+
 #[allow(non_upper_case_globals)]
 static mut enable_progress : bool = false;
 
@@ -35,6 +42,15 @@ pub fn set_colours(opt: bool) {
   colored::control::set_override(opt);
 }
 
+pub fn parse_output_format(val: Option<String>) -> Result<OutputFormat, ::Error> {
+  return match val.as_ref().map(|v| v.as_str()) {
+    None | Some("text") => Ok(OutputFormat::Text),
+    Some("json") => Ok(OutputFormat::JSON),
+    Some("jsonl") => Ok(OutputFormat::JSONLines),
+    Some(v) => Err(format!("invalid output format: {}", v)),
+  };
+}
+
 pub fn print_progress_step(step: u32, steps_total: u32, msg: &str) {
   unsafe {
     if !enable_progress {
@@ -77,16 +93,28 @@ pub fn print_debug(msg: &str) {
   res.expect("cannot write to stderr");
 }
 
+pub fn print_warning(msg: &str) {
+  let res = writeln!(
+      &mut std::io::stderr(),
+      "{}",
+      format!("WARNING: {}", msg).yellow());
+
+  res.expect("cannot write to stderr");
+}
+
 pub fn print_success(msg: &str) {
   println!("{}", msg.green());
 }
 
 pub fn print_repository_path(path: &str) {
-  if let Ok(path) = std::fs::canonicalize(std::path::Path::new(&path)) {
-    println!("Repository: {}", path.to_str().unwrap_or("ERROR"));
-  } else {
-    println!("Repository: {}", path);
-  }
+  println!("Repository: {}", format_repository_path(path));
+}
+
+pub fn format_repository_path(path: &str) -> String {
+  return match std::fs::canonicalize(std::path::Path::new(&path)) {
+    Ok(path) => path.to_str().unwrap_or("ERROR").to_owned(),
+    Err(_) => path.to_owned(),
+  };
 }
 
 pub fn print_repository_size(snap: &::IndexSnapshot) {
@@ -108,12 +136,55 @@ pub fn print_snapshot_time(timestamp_us: i64) {
   println!("Last Snapshot: {}", time.rfc822z());
 }
 
+pub fn print_verification_time(timestamp_us: i64) {
+  let time = time::at(time::Timespec::new(timestamp_us / 1_000_000, 0));
+  println!("Oldest Verification: {}", time.rfc822z());
+}
+
+pub fn print_verification_sample(files_verified: u64, files_total: u64) {
+  println!("Files Verified: {} of {}", files_verified, files_total);
+}
+
 pub fn print_diff(diff: &::index_diff::IndexDiffList) {
-  let mut diff = diff.to_owned();
   if diff.len() == 0 {
     return;
   }
 
+  let diff = sort_diff(diff);
+
+  print!("\n");
+
+  for d in diff {
+    let msg = match d {
+      ::index_diff::IndexDiff::Created{..} => format_diff(&d).green(),
+      ::index_diff::IndexDiff::Deleted{..} => format_diff(&d).red(),
+      _ => format_diff(&d).yellow(),
+    };
+
+    println!("{}", msg);
+  }
+
+  print!("\n");
+}
+
+pub fn format_diff(d: &::index_diff::IndexDiff) -> String {
+  return match d {
+    &::index_diff::IndexDiff::Created{ref file} =>
+     format!("    created  {:?}", file),
+    &::index_diff::IndexDiff::Deleted{ref file} =>
+     format!("    deleted  {:?}", file),
+    &::index_diff::IndexDiff::Modified{ref file} =>
+     format!("    modified {:?}", file),
+    &::index_diff::IndexDiff::MetadataModified{ref file} =>
+     format!("    modified {:?} (metadata modifications only)", file),
+    &::index_diff::IndexDiff::Renamed{ref from, ref to} =>
+      format!("    renamed  {:?} -> {:?}", from, to),
+  };
+}
+
+pub fn sort_diff(diff: &::index_diff::IndexDiffList) -> ::index_diff::IndexDiffList {
+  let mut diff = diff.to_owned();
+
   let sort_name = |d: &::index_diff::IndexDiff| match d {
     &::index_diff::IndexDiff::Deleted{ref file} => file.to_owned(),
     &::index_diff::IndexDiff::Modified{ref file} => file.to_owned(),
@@ -133,27 +204,15 @@ pub fn print_diff(diff: &::index_diff::IndexDiffList) {
   };
 
   diff.sort_by(|a, b| sort_rank(&a).cmp(&sort_rank(&b)));
+  return diff;
+}
 
-  print!("\n");
-
-  for d in diff {
-    let msg = match d {
-      ::index_diff::IndexDiff::Created{ref file} =>
-       format!("    created  {:?}", file).green(),
-      ::index_diff::IndexDiff::Deleted{ref file} =>
-       format!("    deleted  {:?}", file).red(),
-      ::index_diff::IndexDiff::Modified{ref file} =>
-       format!("    modified {:?}", file).yellow(),
-      ::index_diff::IndexDiff::MetadataModified{ref file} =>
-       format!("    modified {:?} (metadata modifications only)", file).yellow(),
-      ::index_diff::IndexDiff::Renamed{ref from, ref to} =>
-        format!("    renamed  {:?} -> {:?}", from, to).yellow()
-    };
-
-    println!("{}", msg);
+pub fn print_check_status(state: &str, summary: &str, perfdata: &Vec<String>) {
+  if perfdata.len() == 0 {
+    println!("IC {} - {}", state, summary);
+  } else {
+    println!("IC {} - {} | {}", state, summary, perfdata.join(" "));
   }
-
-  print!("\n");
 }
 
 pub fn confirm_diffs(diff: &::index_diff::IndexDiffList) -> bool {
@@ -178,25 +237,467 @@ pub fn print_confirmed_diffs(diff: &::index_diff::IndexDiffList) {
   print_diff(diff);
 }
 
+pub fn print_import_report(imported: u64, missing: &Vec<String>, unlisted: &Vec<String>) {
+  println!("Imported: {} files", imported);
+  println!("Missing: {} files (listed, but not found)", missing.len());
+  println!("Unlisted: {} files (found, but not listed)", unlisted.len());
+
+  if missing.len() + unlisted.len() == 0 {
+    return;
+  }
+
+  print!("\n");
+
+  for path in missing {
+    println!("{}", format!("    missing  {:?}", path).red());
+  }
+
+  for path in unlisted {
+    println!("{}", format!("    unlisted {:?}", path).yellow());
+  }
+
+  print!("\n");
+}
+
+pub fn print_audit_result(result: &::index_audit::AuditResult) {
+  println!("Files matched: {}", result.matched.len());
+  println!("Files moved: {}", result.moved.len());
+  println!("New files found: {}", result.new.len());
+  println!("Known files not found: {}", result.missing.len());
+
+  if result.is_clean() {
+    return;
+  }
+
+  print!("\n");
+
+  for &(ref from, ref to) in &result.moved {
+    println!("{}", format!("    moved    {:?} -> {:?}", from, to).yellow());
+  }
+
+  for path in &result.new {
+    println!("{}", format!("    new      {:?}", path).green());
+  }
+
+  for path in &result.missing {
+    println!("{}", format!("    missing  {:?}", path).red());
+  }
+
+  print!("\n");
+}
+
+pub fn print_compare_snapshots(
+    snap_ref: &::IndexReference,
+    other_ref: &::IndexReference,
+    other_index_path: &str,
+    in_sync: bool) {
+  let format_ref = |r: &::IndexReference| {
+    let time = time::at(time::Timespec::new(r.timestamp_us / 1_000_000, 0));
+    return format!("{} ({})", r.checksum, time.rfc822z());
+  };
+
+  println!("Snapshot: {}", format_ref(snap_ref));
+  println!("Other Snapshot: {}", format_ref(other_ref));
+
+  let changed = if in_sync {
+    "-".to_owned()
+  } else if snap_ref.timestamp_us > other_ref.timestamp_us {
+    "this index".to_owned()
+  } else if snap_ref.timestamp_us < other_ref.timestamp_us {
+    format!("other index ({})", other_index_path)
+  } else {
+    "unknown (both snapshots have the same time)".to_owned()
+  };
+
+  println!("Changed most recently: {}", changed);
+}
+
+pub fn print_compare_result(result: &::index_compare::CompareResult) {
+  println!("Files matched: {}", result.matched);
+  println!("Files missing: {}", result.missing.len());
+  println!("Files differing: {}", result.differing.len());
+  println!("Files renamed: {}", result.renamed.len());
+  println!("Extra files found: {}", result.extra.len());
+
+  if result.is_clean() {
+    return;
+  }
+
+  print!("\n");
+
+  for path in &result.missing {
+    println!("{}", format!("    missing  {:?}", path).red());
+  }
+
+  for path in &result.differing {
+    println!("{}", format!("    differs  {:?}", path).red());
+  }
+
+  for &(ref from, ref to) in &result.renamed {
+    println!("{}", format!("    renamed  {:?} -> {:?}", from, to).yellow());
+  }
+
+  for path in &result.extra {
+    println!("{}", format!("    extra    {:?}", path).green());
+  }
+
+  print!("\n");
+}
+
+pub fn print_recover_result(result: &::index_recover::RecoverResult, staging_path: &str) {
+  println!("Staging Directory: {}", format_repository_path(staging_path));
+  println!("Files recovered: {}", result.found.len());
+  println!("Files not found: {}", result.not_found.len());
+
+  if result.found.len() + result.not_found.len() == 0 {
+    return;
+  }
+
+  print!("\n");
+
+  for &(ref target, ref source) in &result.found {
+    if target == source {
+      println!("{}", format!("    recovered {:?}", target).green());
+    } else {
+      println!("{}", format!("    recovered {:?} (from {:?})", target, source).green());
+    }
+  }
+
+  for path in &result.not_found {
+    println!("{}", format!("    not found {:?}", path).red());
+  }
+
+  print!("\n");
+}
+
+pub fn print_protect_result(
+    protected: u64,
+    skipped: u64,
+    parity_bytes: u64,
+    mismatched: &Vec<String>) {
+  println!("Files protected: {}", protected);
+  println!("Files already protected: {}", skipped);
+  println!("Files not matching the snapshot: {}", mismatched.len());
+  println!("Recovery data written: {}", format_bytecount(parity_bytes));
+
+  if mismatched.len() == 0 {
+    return;
+  }
+
+  print!("\n");
+
+  for path in mismatched {
+    println!("{}", format!("    mismatch {:?}", path).red());
+  }
+
+  print!("\n");
+}
+
+pub fn print_repair_result(path: &str, result: &::parity::RepairResult) {
+  println!("File: {:?}", path);
+  println!("Damaged blocks: {} of {}", result.damaged_blocks, result.data_blocks);
+  println!(
+      "Intact recovery blocks: {} of {}",
+      result.parity_blocks_intact,
+      result.parity_blocks);
+}
+
+pub fn print_text_diff(label_old: &str, label_new: &str, lines: &Vec<String>) {
+  println!("{}", format!("--- {}", label_old).bold());
+  println!("{}", format!("+++ {}", label_new).bold());
+
+  for line in lines {
+    let line = match line.chars().next() {
+      Some('-') => line.red(),
+      Some('+') => line.green(),
+      Some('@') => line.cyan(),
+      _ => line.normal(),
+    };
+
+    println!("{}", line);
+  }
+}
+
+pub fn print_known_bad(matches: &Vec<::denylist::DenylistMatch>) {
+  if matches.len() == 0 {
+    return;
+  }
+
+  println!("{}", format!("Known-bad files found: {}", matches.len()).red().bold());
+  print!("\n");
+
+  for m in matches {
+    println!("{}", format!("    known-bad {:?} (listed in {})", m.file, m.list).red().bold());
+  }
+
+  print!("\n");
+}
+
 pub fn print_snapshot_table(index: &::IndexDirectory) -> Result<(), ::Error> {
   for snap_ref in index.list() {
-    println!("{}", format!("snapshot {}", snap_ref.checksum).yellow());
-
     let snap = index.load(snap_ref)?;
-    let snap_time = time::at(time::Timespec::new(snap_ref.timestamp_us / 1_000_000, 0));
+    print_snapshot_summary(snap_ref, &snap);
+  }
+
+  return Ok(());
+}
+
+pub fn print_snapshot_summary(snap_ref: &::IndexReference, snap: &::IndexSnapshot) {
+  println!("{}", format!("snapshot {}", snap_ref.checksum).yellow());
+
+  let snap_time = time::at(time::Timespec::new(snap_ref.timestamp_us / 1_000_000, 0));
+
+  println!("Timestamp: {}", snap_time.rfc822z());
+  println!(
+      "Size: {} ({} files)",
+      format_bytecount(snap.total_size_bytes()),
+      snap.total_file_count());
+
+  println!(
+      "\n    {}\n",
+      snap.message.as_ref().map(|m| m.as_str()).unwrap_or("<no message>"));
+}
+
+pub fn print_snapshot_details(snap_ref: &::IndexReference, snap: &::IndexSnapshot) {
+  let snap_time = time::at(time::Timespec::new(snap_ref.timestamp_us / 1_000_000, 0));
+
+  println!("{}", format!("snapshot {}", snap_ref.checksum).yellow());
+  println!("Timestamp: {} ({})", snap_time.rfc822z(), snap_ref.timestamp_us);
+  println!(
+      "Checksum Function: {}",
+      ::checksum::checksum_function_to_str(&snap.checksum_function));
+  println!(
+      "Size: {} ({} files)",
+      format_bytecount(snap.total_size_bytes()),
+      snap.total_file_count());
+
+  println!(
+      "\n    {}\n",
+      snap.message.as_ref().map(|m| m.as_str()).unwrap_or("<no message>"));
+}
 
-    println!("Timestamp: {}", snap_time.rfc822z());
+pub fn print_file_list(files: &Vec<(&String, &::IndexFileInfo)>) {
+  for &(fpath, finfo) in files {
+    let mtime = match finfo.modified_timestamp_us {
+      Some(t) => time::at(time::Timespec::new(t / 1_000_000, 0)).rfc3339().to_string(),
+      None => "-".into(),
+    };
+
+    println!(
+        "{} {:>12} {} {}",
+        finfo.checksum.as_ref().map(|c| c.as_str()).unwrap_or("-"),
+        finfo.size_bytes,
+        mtime,
+        ::index::encode_string(fpath));
+  }
+}
+
+pub fn print_duplicates(duplicates: &Vec<::index_duplicates::DuplicateGroup>) {
+  let wasted_total = duplicates
+      .iter()
+      .fold(0, |acc, g| acc + g.wasted_bytes());
+
+  println!(
+      "Duplicates: {} groups, {} reclaimable",
+      duplicates.len(),
+      format_bytecount(wasted_total));
+
+  for group in duplicates {
     println!(
-        "Size: {} ({} files)",
-        format_bytecount(snap.total_size_bytes()),
-        snap.total_file_count());
+        "\n{}",
+        format!(
+            "{} reclaimable ({} copies of {}) {}",
+            format_bytecount(group.wasted_bytes()),
+            group.files.len(),
+            format_bytecount(group.size_bytes),
+            group.checksum).yellow());
+
+    for file in &group.files {
+      println!("    {:?}", file);
+    }
+  }
+
+  print!("\n");
+}
 
-    println!("\n    {}\n", snap.message.unwrap_or("<no message>".into()));
+pub fn print_duplicates_json(
+    format: &OutputFormat,
+    duplicates: &Vec<::index_duplicates::DuplicateGroup>) {
+  let wasted_total = duplicates
+      .iter()
+      .fold(0, |acc, g| acc + g.wasted_bytes());
+
+  let summary = vec!(
+    ("total_groups", duplicates.len().to_string()),
+    ("wasted_bytes", wasted_total.to_string()),
+  );
+
+  let groups = duplicates
+      .iter()
+      .map(|g| ::json::duplicate_group_fields(g))
+      .collect();
+
+  print_json_document(format, summary, ("groups", "group"), groups);
+}
+
+pub fn print_status_json(
+    format: &OutputFormat,
+    summary: Vec<(&str, String)>,
+    diff: &::index_diff::IndexDiffList) {
+  let diffs = sort_diff(diff)
+      .iter()
+      .map(|d| ::json::diff_fields(d))
+      .collect();
+
+  print_json_document(format, summary, ("diffs", "diff"), diffs);
+}
+
+pub fn print_snapshot_table_json(
+    format: &OutputFormat,
+    data_path: &str,
+    index: &::IndexDirectory) -> Result<(), ::Error> {
+  let summary = vec!(
+    ("repository", ::json::encode_string(&format_repository_path(data_path))),
+  );
+
+  let mut snapshots = Vec::<Vec<(&str, String)>>::new();
+  for snap_ref in index.list() {
+    let snap = index.load(snap_ref)?;
+    snapshots.push(::json::snapshot_fields(snap_ref, &snap));
   }
 
+  print_json_document(format, summary, ("snapshots", "snapshot"), snapshots);
   return Ok(());
 }
 
+pub fn print_file_list_json(format: &OutputFormat, snap: &::IndexSnapshot) {
+  let checksum_function = ::checksum::checksum_function_to_str(&snap.checksum_function);
+  let summary = vec!(
+    ("checksum_function", ::json::encode_string(&checksum_function)),
+    ("total_files", snap.total_file_count().to_string()),
+    ("total_bytes", snap.total_size_bytes().to_string()),
+  );
+
+  let files = snap
+      .files
+      .iter()
+      .map(|(fpath, finfo)| ::json::file_fields(fpath, finfo))
+      .collect();
+
+  print_json_document(format, summary, ("files", "file"), files);
+}
+
+/**
+ * Print a summary object followed by a list of items. In JSON mode, the items
+ * are stored in the summary object under the given key. In JSON Lines mode, the
+ * summary and every item are printed as separate records, distinguished by
+ * their 'type' field.
+ */
+fn print_json_document(
+    format: &OutputFormat,
+    summary: Vec<(&str, String)>,
+    item_names: (&str, &str),
+    items: Vec<Vec<(&str, String)>>) {
+  let (items_key, item_type) = item_names;
+
+  match format {
+    &OutputFormat::JSONLines => {
+      let mut record = vec!(("type", ::json::encode_string("summary")));
+      record.extend(summary);
+      println!("{}", ::json::encode_object(&record));
+
+      for item in items {
+        let mut record = vec!(("type", ::json::encode_string(item_type)));
+        record.extend(item);
+        println!("{}", ::json::encode_object(&record));
+      }
+    },
+    _ => {
+      let items : Vec<String> = items
+          .iter()
+          .map(|i| ::json::encode_object(i))
+          .collect();
+
+      let mut document = summary;
+      document.push((items_key, ::json::encode_list(&items)));
+      println!("{}", ::json::encode_object(&document));
+    },
+  };
+}
+
+pub fn print_usage_table(
+    usage: &::index_usage::DirectoryUsageMap,
+    usage_prev: Option<&::index_usage::DirectoryUsageMap>) {
+  let mut dirs : Vec<&::std::path::PathBuf> = usage.keys().collect();
+  if let Some(usage_prev) = usage_prev {
+    dirs.extend(usage_prev.keys().filter(|d| !usage.contains_key(*d)));
+    dirs.sort();
+  }
+
+  let empty = ::index_usage::DirectoryUsage::default();
+  for dir in dirs {
+    let entry = usage.get(dir).unwrap_or(&empty);
+    let dir_name = if dir.as_os_str().is_empty() {
+      ".".to_owned()
+    } else {
+      dir.to_string_lossy().into_owned()
+    };
+
+    match usage_prev {
+      Some(usage_prev) => {
+        let entry_prev = usage_prev.get(dir).unwrap_or(&empty);
+        println!(
+            "{:>12} {:>13} {:>8} files {:>9}  {}",
+            format_bytecount(entry.size_bytes),
+            format_bytecount_delta(entry.size_bytes, entry_prev.size_bytes),
+            entry.file_count,
+            format!("{:+}", entry.file_count as i64 - entry_prev.file_count as i64),
+            dir_name);
+      },
+      None =>
+        println!(
+            "{:>12} {:>8} files  {}",
+            format_bytecount(entry.size_bytes),
+            entry.file_count,
+            dir_name),
+    };
+  }
+}
+
+pub fn parse_bytecount(val: &str) -> Result<u64, ::Error> {
+  let (num, exp) = match val.chars().last() {
+    Some('K') | Some('k') => (&val[..val.len() - 1], 10),
+    Some('M') | Some('m') => (&val[..val.len() - 1], 20),
+    Some('G') | Some('g') => (&val[..val.len() - 1], 30),
+    Some('T') | Some('t') => (&val[..val.len() - 1], 40),
+    _ => (val, 0),
+  };
+
+  return match num.parse::<u64>().ok().and_then(|v| v.checked_mul(u64::pow(2, exp))) {
+    Some(v) => Ok(v),
+    None => Err(format!("invalid size: {}", val)),
+  };
+}
+
+/**
+ * Parse a duration such as '90s', '30m', '4h' or '2d' into seconds; a number
+ * without a unit is in seconds
+ */
+pub fn parse_duration(val: &str) -> Result<u64, ::Error> {
+  let (num, unit) = match val.chars().last() {
+    Some('s') => (&val[..val.len() - 1], 1),
+    Some('m') => (&val[..val.len() - 1], 60),
+    Some('h') => (&val[..val.len() - 1], 60 * 60),
+    Some('d') => (&val[..val.len() - 1], 24 * 60 * 60),
+    _ => (val, 1),
+  };
+
+  return match num.parse::<u64>() {
+    Ok(v) => Ok(v * unit),
+    Err(_) => Err(format!("invalid duration: {}", val)),
+  };
+}
+
 fn format_bytecount(val: u64) -> String {
   if val < u64::pow(2, 10) {
     return format!("{}B", val);
@@ -211,6 +712,14 @@ fn format_bytecount(val: u64) -> String {
   }
 }
 
+fn format_bytecount_delta(val: u64, val_prev: u64) -> String {
+  if val >= val_prev {
+    return format!("+{}", format_bytecount(val - val_prev));
+  } else {
+    return format!("-{}", format_bytecount(val_prev - val));
+  }
+}
+
 pub fn print_scanprogress(
     files_scanned: u64,
     bytes_scanned: u64,
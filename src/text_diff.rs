@@ -0,0 +1,99 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+
+/* the maximum number of cells in the LCS table */
+const MAX_DIFF_CELLS : usize = 1 << 24;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edit {
+  Equal,
+  Delete,
+  Insert,
+}
+
+/**
+ * Compute a line-based diff in the unified format with 'context' lines of
+ * context. Returns the hunks (including the '@@' headers), or None if the
+ * inputs are too large to be compared
+ */
+pub fn unified_diff(old: &str, new: &str, context: usize) -> Option<Vec<String>> {
+  let a : Vec<&str> = old.lines().collect();
+  let b : Vec<&str> = new.lines().collect();
+
+  if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_DIFF_CELLS {
+    return None;
+  }
+
+  /* lcs[i][j] is the length of the longest common subsequence of a[i..], b[j..] */
+  let width = b.len() + 1;
+  let mut lcs = vec!(0u32; (a.len() + 1) * width);
+  for i in (0..a.len()).rev() {
+    for j in (0..b.len()).rev() {
+      lcs[i * width + j] = if a[i] == b[j] {
+        lcs[(i + 1) * width + j + 1] + 1
+      } else {
+        ::std::cmp::max(lcs[(i + 1) * width + j], lcs[i * width + j + 1])
+      };
+    }
+  }
+
+  /* each edit is stored along with the line numbers before it is applied */
+  let mut edits = Vec::<(Edit, usize, usize)>::new();
+  let (mut i, mut j) = (0, 0);
+  while i < a.len() || j < b.len() {
+    if i < a.len() && j < b.len() && a[i] == b[j] {
+      edits.push((Edit::Equal, i, j));
+      i += 1;
+      j += 1;
+    } else if i < a.len() && (j == b.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+      edits.push((Edit::Delete, i, j));
+      i += 1;
+    } else {
+      edits.push((Edit::Insert, i, j));
+      j += 1;
+    }
+  }
+
+  /* group the changes into hunks, merging hunks whose context overlaps */
+  let changes : Vec<usize> = (0..edits.len())
+      .filter(|&k| edits[k].0 != Edit::Equal)
+      .collect();
+
+  let mut ranges = Vec::<(usize, usize)>::new();
+  for k in changes {
+    let start = k.saturating_sub(context);
+    let end = ::std::cmp::min(k + context + 1, edits.len());
+
+    match ranges.last_mut() {
+      Some(ref mut last) if start <= last.1 => last.1 = end,
+      _ => ranges.push((start, end)),
+    };
+  }
+
+  let mut lines = Vec::<String>::new();
+  for (start, end) in ranges {
+    let hunk = &edits[start..end];
+    let old_count = hunk.iter().filter(|e| e.0 != Edit::Insert).count();
+    let new_count = hunk.iter().filter(|e| e.0 != Edit::Delete).count();
+    let old_start = if old_count > 0 { hunk[0].1 + 1 } else { hunk[0].1 };
+    let new_start = if new_count > 0 { hunk[0].2 + 1 } else { hunk[0].2 };
+
+    lines.push(format!("@@ -{},{} +{},{} @@", old_start, old_count, new_start, new_count));
+
+    for &(edit, i, j) in hunk {
+      lines.push(match edit {
+        Edit::Equal => format!(" {}", a[i]),
+        Edit::Delete => format!("-{}", a[i]),
+        Edit::Insert => format!("+{}", b[j]),
+      });
+    }
+  }
+
+  return Some(lines);
+}
@@ -0,0 +1,218 @@
+/**
+ * integritycheck - https://github.com/asmuth/integritycheck
+ * Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+ *
+ * This file is part of the "integritycheck" project. integritycheck is free software
+ * licensed under the Apache License, Version 2.0 (the "License"); you may not
+ * use this file except in compliance with the License.
+ */
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read,Write};
+use std::path::PathBuf;
+use deflate;
+use inflate;
+
+pub const VERIFY_DIRNAME : &'static str = "verify";
+const STATE_FILENAME : &'static str = "state";
+const CHECKPOINT_FILENAME : &'static str = "checkpoint";
+
+pub const DEFAULT_CHECKPOINT_INTERVAL_SECS : u64 = 60;
+
+/**
+ * The time at which each file was last verified. An entry is only valid as long
+ * as the file has the checksum it had when it was verified
+ */
+pub struct VerifyState {
+  files: HashMap<String, (i64, String)>,
+}
+
+impl VerifyState {
+
+  pub fn load(index: &::IndexDirectory) -> Result<VerifyState, ::Error> {
+    let mut state = VerifyState {
+      files: HashMap::new(),
+    };
+
+    let path = state_path(index);
+    if !path.exists() {
+      return Ok(state);
+    }
+
+    let mut data = String::new();
+    if let Err(e) = fs::File::open(&path).and_then(|mut f| f.read_to_string(&mut data)) {
+      return Err(format!("error while reading {:?}: {}", path, e));
+    }
+
+    for line in data.lines() {
+      let fields : Vec<&str> = line.splitn(3, ' ').collect();
+      if fields.len() != 3 {
+        return Err(format!("invalid verify state file: {:?}", path));
+      }
+
+      let timestamp_us = match fields[0].parse::<i64>() {
+        Ok(v) => v,
+        Err(_) => return Err(format!("invalid verify state file: {:?}", path)),
+      };
+
+      let file_path = ::index::decode_string(fields[2])?;
+      state.files.insert(file_path, (timestamp_us, fields[1].to_owned()));
+    }
+
+    return Ok(state);
+  }
+
+  /**
+   * Write the state to the index directory, keeping only the entries for the
+   * files in the snapshot. The file is replaced atomically
+   */
+  pub fn save(
+      self: &Self,
+      index: &::IndexDirectory,
+      snapshot: &::IndexSnapshot) -> Result<(), ::Error> {
+    let mut data = String::new();
+    for (file_path, finfo) in snapshot.files.iter() {
+      if let (Some(timestamp_us), &Some(ref checksum)) = (self.last_verified(file_path, finfo), &finfo.checksum) {
+        data += &format!(
+            "{} {} {}\n",
+            timestamp_us,
+            checksum,
+            ::index::encode_string(file_path));
+      }
+    }
+
+    let path = state_path(index);
+    let path_tmp = path.with_extension("tmp");
+    let result =
+        fs::create_dir_all(index.verify_path())
+        .and_then(|_| fs::File::create(&path_tmp))
+        .and_then(|mut f| f.write_all(data.as_bytes()).and_then(|_| f.sync_all()))
+        .and_then(|_| fs::rename(&path_tmp, &path));
+
+    return match result {
+      Ok(_) => Ok(()),
+      Err(e) => Err(format!("error while writing verify state file: {}", e)),
+    };
+  }
+
+  pub fn last_verified(self: &Self, file_path: &str, finfo: &::IndexFileInfo) -> Option<i64> {
+    return match (self.files.get(file_path), &finfo.checksum) {
+      (Some(&(timestamp_us, ref c1)), &Some(ref c2)) if c1 == c2 => Some(timestamp_us),
+      _ => None,
+    };
+  }
+
+  pub fn set_verified(self: &mut Self, file_path: &str, checksum: &str, timestamp_us: i64) {
+    self.files.insert(file_path.to_owned(), (timestamp_us, checksum.to_owned()));
+  }
+
+  /**
+   * List the files of a snapshot in the order in which they should be verified:
+   * files that were never verified first, then the least recently verified ones
+   */
+  pub fn order(self: &Self, snapshot: &::IndexSnapshot) -> Vec<String> {
+    let mut files : Vec<(Option<i64>, &String)> = snapshot
+        .files
+        .iter()
+        .map(|(fpath, finfo)| (self.last_verified(fpath, finfo), fpath))
+        .collect();
+
+    files.sort();
+    return files.into_iter().map(|(_, fpath)| fpath.to_owned()).collect();
+  }
+
+  /**
+   * The time of the least recent verification of any file in the snapshot.
+   * Files that were never verified count from 'snapshot_timestamp_us'
+   */
+  pub fn oldest(self: &Self, snapshot: &::IndexSnapshot, snapshot_timestamp_us: i64) -> Option<i64> {
+    return snapshot
+        .files
+        .iter()
+        .map(|(fpath, finfo)| self.last_verified(fpath, finfo).unwrap_or(snapshot_timestamp_us))
+        .min();
+  }
+
+}
+
+/**
+ * Save the checksums that a verify run against the snapshot 'snapshot_ref' has
+ * computed so far, so that the run can be resumed if it is interrupted
+ */
+pub fn save_checkpoint(
+    index: &::IndexDirectory,
+    snapshot_ref: &::IndexReference,
+    snapshot: &::IndexSnapshot) -> Result<(), ::Error> {
+  let mut checkpoint = ::IndexSnapshot::new(snapshot.checksum_function.to_owned());
+  for (file_path, finfo) in snapshot.files.iter() {
+    if finfo.checksum.is_some() {
+      checkpoint.update(file_path, finfo);
+    }
+  }
+
+  ::prompt::print_debug(&format!(
+      "Writing checkpoint with {} files",
+      checkpoint.total_file_count()));
+
+  let data = deflate::deflate_bytes_zlib(&checkpoint.encode(snapshot_ref.timestamp_us));
+  let path = checkpoint_path(index);
+  let path_tmp = path.with_extension("tmp");
+  let result =
+      fs::create_dir_all(index.verify_path())
+      .and_then(|_| fs::File::create(&path_tmp))
+      .and_then(|mut f| f.write_all(&data).and_then(|_| f.sync_all()))
+      .and_then(|_| fs::rename(&path_tmp, &path));
+
+  return match result {
+    Ok(_) => Ok(()),
+    Err(e) => Err(format!("error while writing checkpoint file: {}", e)),
+  };
+}
+
+/**
+ * Load the checksums saved by an interrupted verify run against the snapshot
+ * 'snapshot_ref', or None if there is no checkpoint
+ */
+pub fn load_checkpoint(
+    index: &::IndexDirectory,
+    snapshot_ref: &::IndexReference) -> Result<Option<::IndexSnapshot>, ::Error> {
+  let path = checkpoint_path(index);
+  if !path.exists() {
+    return Ok(None);
+  }
+
+  let mut data_compressed = Vec::<u8>::new();
+  if let Err(e) = fs::File::open(&path).and_then(|mut f| f.read_to_end(&mut data_compressed)) {
+    return Err(format!("error while reading {:?}: {}", path, e));
+  }
+
+  let data = match inflate::inflate_bytes_zlib(&data_compressed) {
+    Ok(data) => data,
+    Err(e) => return Err(format!("invalid checkpoint file {:?}: {}", path, e)),
+  };
+
+  return match ::IndexSnapshot::decode(&data, snapshot_ref.timestamp_us) {
+    Ok(checkpoint) => Ok(Some(checkpoint)),
+    Err(_) => Err(format!("the checkpoint does not belong to snapshot {}", snapshot_ref.checksum)),
+  };
+}
+
+pub fn remove_checkpoint(index: &::IndexDirectory) -> Result<(), ::Error> {
+  let path = checkpoint_path(index);
+  if !path.exists() {
+    return Ok(());
+  }
+
+  return match fs::remove_file(&path) {
+    Ok(_) => Ok(()),
+    Err(e) => Err(format!("error while removing checkpoint file: {}", e)),
+  };
+}
+
+fn checkpoint_path(index: &::IndexDirectory) -> PathBuf {
+  return index.verify_path().join(CHECKPOINT_FILENAME);
+}
+
+fn state_path(index: &::IndexDirectory) -> PathBuf {
+  return index.verify_path().join(STATE_FILENAME);
+}
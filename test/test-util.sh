@@ -1,6 +1,7 @@
 TEST_SRCDIR="$(dirname "$(realpath "$0")")"
 TEST_TMPDIR="$(mktemp -d "/tmp/ic-test-XXXXXXX")"
 trap "rm -rf ${TEST_TMPDIR};" EXIT
+export XDG_CONFIG_HOME="${TEST_TMPDIR}/config"
 
 print_yellow () {
   printf "\033[1;33m%s\033[0m" "$1"
@@ -0,0 +1,61 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir -p "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+mkdir -p a/b/c a/bb other
+for f in a/file a/b/file a/b/c/file a/bb/file other/file; do
+  echo "1" > "${f}"
+done
+
+ic init --progress=off --set_time 1451624401000000
+
+for f in a/file a/b/file a/b/c/file a/bb/file other/file; do
+  echo "22" > "${f}"
+done
+
+echo "3" > a/b/c/new
+echo "3" > a/bb/new
+echo "3" > other/new
+
+# only the changes below the given paths are acknowledged
+ic ack -y --set_time 1451624402000000 a/b ./other/new
+
+ic ls-files | awk '{ print $2, $4 }' > ../files
+
+(cat > "../files.expected") <<EOF
+3 a/b/c/file
+2 a/b/c/new
+3 a/b/file
+2 a/bb/file
+2 a/file
+2 other/file
+2 other/new
+EOF
+
+diff ../files ../files.expected
+
+if ic status --colours=off > ../status; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+grep -q '"a/bb/new"' ../status
+grep -q '"a/bb/file"' ../status
+grep -q '"other/file"' ../status
+if grep -q '"a/b/' ../status; then
+  echo "changes below a/b must be acknowledged"
+  exit 1
+fi
+
+# without paths, all changes are acknowledged
+ic ack -y --set_time 1451624403000000
+ic status
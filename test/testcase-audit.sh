@@ -0,0 +1,54 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+echo "A" > testA
+echo "B" > testB
+echo "C" > testC
+sha256sum testA testB testC > ../known.sha256
+
+ic init --progress=off
+ic audit --known=../known.sha256 > ../audit.out
+grep -qx "Files matched: 3" ../audit.out
+
+mv testB testB2
+echo "D" > testD
+rm testC
+
+if ic audit --colours=off --known=../known.sha256 > ../audit.out; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+(cat > "../audit.expected") <<EOF
+Files matched: 1
+Files moved: 1
+New files found: 1
+Known files not found: 1
+
+    moved    "testB" -> "testB2"
+    new      "testD"
+    missing  "testC"
+
+EOF
+
+diff ../audit.out ../audit.expected
+
+# the snapshot still contains the original files
+snapshot="$(ic log | grep "^snapshot" | head -n 1 | cut -d " " -f 2)"
+ic audit --known=../known.sha256 --snapshot="${snapshot}" > ../audit.out
+grep -qx "Files matched: 3" ../audit.out
+
+if ic audit --known=../known.sha256 --known_format=md5sum; then
+  echo "invalid known hashes must fail"
+  exit 1
+fi
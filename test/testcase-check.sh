@@ -0,0 +1,77 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+echo "A" > testA
+echo "B" > testB
+touch -m --date='2016-01-01 06:00:01' testA testB
+
+ic init --progress=off --set_time 1451624401000000
+
+ic check --set_time 1451628001000000 > "../output"
+
+(cat > "../output.expected") <<EOF
+IC OK - no changes, snapshot age 1h | files=2;;;0 bytes=4B;;;0 created=0;0;;0 deleted=0;0;;0 modified=0;;0;0 metadata_modified=0;0;;0 renamed=0;0;;0 age=3600s;;;0
+EOF
+
+diff "../output" "../output.expected"
+
+echo "C" > testC
+
+if ic check --set_time 1451628001000000 > "../output"; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+(cat > "../output.expected") <<EOF
+IC WARNING - 1 created, snapshot age 1h | files=2;;;0 bytes=4B;;;0 created=1;0;;0 deleted=0;0;;0 modified=0;;0;0 metadata_modified=0;0;;0 renamed=0;0;;0 age=3600s;;;0
+EOF
+
+diff "../output" "../output.expected"
+
+ic check --set_time 1451628001000000 -w created:5 -c age:2h
+
+set +e
+ic check --set_time 1451628001000000 -w created:5 -c age:30m > "../output"
+exit_code="$?"
+set -e
+
+if [[ "${exit_code}" -ne 2 ]]; then
+  echo "exit code must be two"
+  exit 1
+fi
+
+grep -q "^IC CRITICAL - 1 created, snapshot age 1h |" "../output"
+
+set +e
+ic check -w invalid:5 > "../output"
+exit_code="$?"
+set -e
+
+if [[ "${exit_code}" -ne 3 ]]; then
+  echo "exit code must be three"
+  exit 1
+fi
+
+grep -q "^IC UNKNOWN - invalid threshold key: invalid$" "../output"
+
+set +e
+ic check -w age:999999999999999999d > "../output"
+exit_code="$?"
+set -e
+
+if [[ "${exit_code}" -ne 3 ]]; then
+  echo "exit code must be three"
+  exit 1
+fi
+
+grep -q "^IC UNKNOWN - invalid age: 999999999999999999d$" "../output"
@@ -0,0 +1,59 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+echo "A" > testA
+echo "B" > testB
+ic init --progress=off --set_time 1451624401000000
+
+# both replicas start out in sync
+cp -r . ../replica
+ic compare-index ../replica/.ic > ../compare.out
+grep -qx "Files matched: 2" ../compare.out
+grep -qx "Changed most recently: -" ../compare.out
+
+# the replica drifts
+echo "X" > ../replica/testB
+echo "C" > ../replica/testC
+ic ack -y -d ../replica --set_time 1451624402000000
+
+set +e
+ic compare-index --colours=off ../replica/.ic > ../compare.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 2
+grep -qx "Changed most recently: other index (../replica/.ic)" ../compare.out
+grep -qx '    differs  "testB"' ../compare.out
+grep -qx '    extra    "testC"' ../compare.out
+
+# the original snapshot of the replica still matches
+snapshot="$(ic log -d ../replica --colours=off | grep "^snapshot" | tail -n 1 | cut -d " " -f 2)"
+ic compare-index --other_snapshot="${snapshot}" ../replica/.ic
+
+# the repository changes after the replica
+rm testA
+ic ack -y --set_time 1451624403000000
+
+set +e
+ic compare-index --colours=off ../replica/.ic > ../compare.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 2
+grep -qx "Changed most recently: this index" ../compare.out
+grep -qx '    extra    "testA"' ../compare.out
+
+if ic compare-index ../nonexistent; then
+  echo "missing index directories must fail"
+  exit 1
+fi
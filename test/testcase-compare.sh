@@ -0,0 +1,78 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+mkdir dir
+echo "A" > testA
+echo "B" > testB
+echo "C" > testC
+echo "D" > dir/testD
+ic init --progress=off
+
+# a plain copy with different modification times matches
+cp -r . ../replica
+touch -m --date='2016-01-01 06:00:01' ../replica/testA
+rm -rf ../replica/.ic
+ic compare ../replica > ../compare.out
+grep -qx "Files matched: 4" ../compare.out
+
+# the replica may have its own index
+cp -r .ic ../replica/.ic
+ic compare ../replica
+
+mv ../replica/testB ../replica/testB2
+echo "X" > ../replica/testC
+rm ../replica/dir/testD
+echo "E" > ../replica/testE
+find ../replica -type f | sort > ../replica.before
+
+set +e
+ic compare --colours=off ../replica > ../compare.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 2
+
+(cat > "../compare.expected") <<EOF
+Files matched: 1
+Files missing: 1
+Files differing: 1
+Files renamed: 1
+Extra files found: 1
+
+    missing  "dir/testD"
+    differs  "testC"
+    renamed  "testB" -> "testB2"
+    extra    "testE"
+
+EOF
+
+diff ../compare.out ../compare.expected
+
+# nothing is written to either side
+find ../replica -type f | sort > ../replica.after
+diff ../replica.before ../replica.after
+ic status
+
+# without differing contents, the exit code is one
+echo "C" > ../replica/testC
+set +e
+ic compare ../replica > /dev/null
+exit_code=$?
+set -e
+
+test "${exit_code}" = 1
+
+if ic compare ../nonexistent; then
+  echo "missing directories must fail"
+  exit 1
+fi
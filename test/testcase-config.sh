@@ -0,0 +1,85 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+mkdir "${TEST_TMPDIR}/other"
+cd "${TEST_TMPDIR}/other"
+
+echo "A" > "../repo/testA"
+echo "B" > "../repo/testB"
+
+# the user-level config sets the repository and index directory
+mkdir -p "${XDG_CONFIG_HOME}/integritycheck"
+(cat > "${XDG_CONFIG_HOME}/integritycheck/config") <<EOF
+data_dir = ${TEST_TMPDIR}/repo
+index_dir = .index
+EOF
+
+ic init --progress=off --set_time 1451624401000000
+test -d ../repo/.index
+ic status
+
+# the repository config sets per-command defaults
+(cat > "../repo/.index/config") <<EOF
+format = json
+log.format = text
+status.verbose = on
+EOF
+
+ic status > ../status.json
+grep -q '"command":"status"' ../status.json
+ic log > ../log.txt
+grep -q "Fri, 01 Jan 2016 06:00:01 +0100" ../log.txt
+
+# the command line takes precedence over the environment and the config files
+IC_FORMAT=jsonl ic status > ../status.jsonl
+grep -q '^{"type":"summary"' ../status.jsonl
+IC_FORMAT=jsonl ic status --format=text > ../status.txt
+grep -q "Repository: ${TEST_TMPDIR}/repo" ../status.txt
+
+# options that mean different things in different commands are only set for
+# the commands that share the meaning, unless they are prefixed with a command
+if IC_FROM=missing ic diff-content 2> ../diff.err; then
+  echo "exit code must be 64"
+  exit 1
+fi
+
+grep -q "no snapshot before" ../diff.err
+echo "diff-content.from = missing" >> "../repo/.index/config"
+if ic diff-content 2> ../diff.err; then
+  echo "exit code must be 64"
+  exit 1
+fi
+
+if grep -q "no snapshot before" ../diff.err; then
+  echo "diff-content.from must be applied"
+  exit 1
+fi
+
+# the config file in the index directory can not move the repository
+cp "../repo/.index/config" ../config.orig
+echo "data_dir = ${TEST_TMPDIR}/other" >> "../repo/.index/config"
+set +e
+ic status 2> ../status.err
+exit_code=$?
+set -e
+
+test "${exit_code}" = 64
+grep -q "'data_dir' can not be set" ../status.err
+cp ../config.orig "../repo/.index/config"
+
+# invalid defaults are reported as usage errors
+echo "status.verbose = maybe" >> "../repo/.index/config"
+set +e
+ic status
+exit_code=$?
+set -e
+
+test "${exit_code}" = 64
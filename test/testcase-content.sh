@@ -0,0 +1,85 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+printf 'a\nb\nc\nd\ne\nf\ng\nh\n' > fstab
+echo "hosts" > hosts
+head -c 2048 /dev/zero > large
+ic init --progress=off --store_content=1K --set_time 1451624401000000
+first="$(ic log --colours=off | grep "^snapshot" | cut -d " " -f 2)"
+
+ic show-content fstab > ../fstab.out
+cmp ../fstab.out fstab
+
+if ic show-content large; then
+  echo "files larger than the limit must not be stored"
+  exit 1
+fi
+
+# the snapshot format is unchanged, so older versions can still read it
+python3 -c '
+import sys, zlib
+for line in zlib.decompress(open(sys.argv[1], "rb").read()).decode().splitlines():
+  assert line.startswith("#") or len(line.split(" ")) == 4, line
+' .ic/*.idx
+
+# the store can be enabled in the config file
+echo "store_content = 1K" >> .ic/config
+printf 'a\nb\nC\nd\ne\nf\ng\nh\nX\n' > fstab
+rm hosts
+echo "new" > resolv.conf
+ic ack -y --set_time 1451624402000000
+
+ic show-content --snapshot="${first}" fstab > ../fstab.out
+test "$(cat ../fstab.out | wc -l)" = 8
+
+ic diff-content --colours=off > ../diff.out
+
+(cat > "../diff.expected") <<EOF
+--- a/fstab
++++ b/fstab
+@@ -1,8 +1,9 @@
+ a
+ b
+-c
++C
+ d
+ e
+ f
+ g
+ h
++X
+--- a/hosts
++++ /dev/null
+@@ -1,1 +0,0 @@
+-hosts
+--- /dev/null
++++ b/resolv.conf
+@@ -0,0 +1,1 @@
++new
+EOF
+
+diff ../diff.out ../diff.expected
+
+# only the given paths
+ic diff-content --colours=off hosts > ../diff.out
+test "$(head -n 1 ../diff.out)" = "--- a/hosts"
+test "$(wc -l < ../diff.out)" = 4
+
+# snapshots without stored contents
+echo "changed" > large
+ic ack -y --store_content=off --set_time 1451624403000000
+ic diff-content --colours=off > ../diff.out
+grep -qx "Contents were not stored" ../diff.out
+
+# the repository is unaffected by the content store
+ic verify --progress=off
@@ -0,0 +1,132 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+listen() {
+  python3 -c '
+import socket, sys
+sock = socket.socket(socket.AF_UNIX, socket.SOCK_DGRAM)
+sock.bind(sys.argv[1])
+sock.settimeout(10)
+with open(sys.argv[2], "wb") as f:
+  for i in range(int(sys.argv[3])):
+    f.write(sock.recv(65536) + b"\n--\n")
+' "$@"
+}
+
+echo "A" > testA
+echo "B" > testB
+ic init --progress=off
+
+echo "EVIL" > ../evil
+(cat > "../bad.txt") <<EOF
+# known-bad hashes
+$(sha256sum ../evil)
+EOF
+
+echo "known_bad = ${TEST_TMPDIR}/bad.txt" >> .ic/config
+ic status
+ic verify
+
+# a new known-bad file
+cp ../evil testC
+
+set +e
+ic status --colours=off > ../status.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 7
+grep -qx "Status: DIRTY" ../status.out
+grep -q "known-bad \"testC\" (listed in ${TEST_TMPDIR}/bad.txt)" ../status.out
+
+set +e
+ic verify --format=json > ../verify.json
+exit_code=$?
+set -e
+
+test "${exit_code}" = 7
+grep -q '"known_bad":\[{"path":"testC",' ../verify.json
+
+# ack refuses to create the snapshot unless overridden
+set +e
+ic ack -y --colours=off > ../ack.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 7
+test "$(ic log --colours=off | grep -c "^snapshot")" = 1
+
+ic ack -y --allow_known_bad
+test "$(ic log --colours=off | grep -c "^snapshot")" = 2
+
+# a known file is still reported after it was acknowledged
+set +e
+ic status > /dev/null
+exit_code=$?
+set -e
+
+test "${exit_code}" = 7
+
+# known-bad files are reported even if there are no other changes
+(cat >> ".ic/config") <<EOF
+notify_on = deleted
+notify_command = cat > ${TEST_TMPDIR}/report.json
+EOF
+
+listen "../journal.sock" "../journal" 2 &
+listener=$!
+sleep 1
+
+set +e
+ic status --log=journald:../journal.sock --prometheus_file=../metrics.prom > /dev/null
+exit_code=$?
+set -e
+
+wait $listener
+
+test "${exit_code}" = 7
+grep -q '"diffs":\[\],"known_bad":\[{"path":"testC",' ../report.json
+grep -qx "MESSAGE=status $(pwd -P): dirty, 1 known-bad" ../journal
+grep -qx "PRIORITY=2" ../journal
+grep -qx "IC_KIND=known_bad" ../journal
+grep -qx "IC_PATH=testC" ../journal
+
+labels="command=\"status\",repository=\"$(pwd -P)\""
+grep -qx "integritycheck_known_bad_files{${labels}} 1" ../metrics.prom
+if grep -q "^integritycheck_last_success_timestamp_seconds" ../metrics.prom; then
+  echo "a run with known-bad files is not a success"
+  exit 1
+fi
+
+rm testC
+ic ack -y
+ic status
+
+# checksums of a different kind than the repository's are an error
+md5sum ../evil > ../bad-md5.txt
+cp .ic/config ../config.orig
+echo "known_bad = ${TEST_TMPDIR}/bad-md5.txt" >> .ic/config
+if ic status 2> ../status.err; then
+  echo "md5 lists must fail in a sha256 repository"
+  exit 1
+fi
+
+grep -q "expected a sha256 checksum" ../status.err
+cp ../config.orig .ic/config
+
+# missing lists are an error
+echo "known_bad = ${TEST_TMPDIR}/missing.txt" >> .ic/config
+if ic status; then
+  echo "missing lists must fail"
+  exit 1
+fi
@@ -0,0 +1,52 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir -p "${TEST_TMPDIR}/repo/a/b" "${TEST_TMPDIR}/repo/c"
+cd "${TEST_TMPDIR}/repo"
+
+echo "hello" > a/x
+echo "hi" > a/b/y
+echo "z" > c/z
+
+ic init --progress=off --set_time 1451624401000000
+
+echo "more" >> a/b/y
+echo "new" > c/n
+ic ack -y --set_time 1451624402000000
+
+ic du > "../output"
+
+(cat > "../output.expected") <<EOF
+         20B        4 files  .
+         14B        2 files  a
+          8B        1 files  a/b
+          6B        2 files  c
+EOF
+
+diff "../output" "../output.expected"
+
+ic du ./a/ --depth=0 > "../output"
+
+(cat > "../output.expected") <<EOF
+         14B        2 files  a
+EOF
+
+diff "../output" "../output.expected"
+
+snapshot_first="$(ls .ic | sort | head -n 1 | cut -d- -f2 | cut -c1-12)"
+ic du --depth=1 --compare="${snapshot_first}" > "../output"
+
+(cat > "../output.expected") <<EOF
+         20B           +9B        4 files        +1  .
+         14B           +5B        2 files        +0  a
+          6B           +4B        2 files        +1  c
+EOF
+
+diff "../output" "../output.expected"
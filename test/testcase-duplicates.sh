@@ -0,0 +1,46 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir -p "${TEST_TMPDIR}/repo/sub"
+cd "${TEST_TMPDIR}/repo"
+
+echo "AAAA" > testA
+echo "AAAA" > sub/testA
+echo "AAAA" > sub/testA2
+echo "B" > testB
+echo "B" > sub/testB
+echo "C" > testC
+touch empty1 empty2
+
+ic init --progress=off
+ic duplicates --colours=off | grep -vE "^Repository" > "../output"
+
+(cat > "../output.expected") <<EOF
+Duplicates: 2 groups, 12B reclaimable
+
+10B reclaimable (3 copies of 5B) 4677942dfa3e74b5dea7484661a2485bb73ba422eb72d311fdb39372c019c615
+    "sub/testA"
+    "sub/testA2"
+    "testA"
+
+2B reclaimable (2 copies of 2B) c0cde77fa8fef97d476c10aad3d2d54fcc2f336140d073651c2dcccf1e379fd6
+    "sub/testB"
+    "testB"
+
+EOF
+
+diff "../output" "../output.expected"
+
+ic duplicates --min_size=3 --format=json > "../output.json"
+(cat > "../output.json.expected") <<EOF
+{"total_groups":1,"wasted_bytes":10,"groups":[{"checksum":"4677942dfa3e74b5dea7484661a2485bb73ba422eb72d311fdb39372c019c615","size_bytes":5,"wasted_bytes":10,"files":["sub/testA","sub/testA2","testA"]}]}
+EOF
+
+diff "../output.json" "../output.json.expected"
@@ -0,0 +1,64 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir -p "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+mkdir -p a/b/c meta/.ic-old skip/deep
+for f in a/file a/b/c/file meta/notes meta/.icx meta/.ic-old/file skip/file skip/deep/file; do
+  echo "1" > "${f}"
+done
+
+# the index directory is nested in the repository and must be excluded, but
+# not its siblings that share a prefix with it
+export IC_INDEX_DIR=meta/.ic
+ic init --progress=off --exclude=skip/ --set_time 1451624401000000
+
+ic ls-files | awk '{ print $4 }' > ../files
+
+(cat > "../files.expected") <<EOF
+a/b/c/file
+a/file
+meta/.ic-old/file
+meta/.icx
+meta/notes
+EOF
+
+diff ../files ../files.expected
+
+# excluded directories are not descended into
+ic status --colours=off -v --exclude=skip/ 2> ../status.err
+grep -qx 'DEBUG Skipping directory: "meta/.ic"' ../status.err
+grep -qx 'DEBUG Ignoring path: "skip"' ../status.err
+if grep -q 'metadata: "\(meta/\.ic/\|skip/\)' ../status.err; then
+  echo "excluded directories must be pruned"
+  exit 1
+fi
+
+ic verify --progress=off --colours=off -v --exclude=skip/ 2> ../verify.err
+grep -qx 'DEBUG Skipping directory: "meta/.ic"' ../verify.err
+if grep -q 'metadata: "\(meta/\.ic/\|skip/\)' ../verify.err; then
+  echo "excluded directories must be pruned"
+  exit 1
+fi
+
+# changes inside the excluded directories are not reported
+echo "22" > skip/deep/file
+echo "22" > meta/.ic-old/file
+if ic status --colours=off --exclude=skip/ > ../status; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+grep -q '"meta/.ic-old/file"' ../status
+if grep -q '"skip/' ../status; then
+  echo "changes in excluded directories must not be reported"
+  exit 1
+fi
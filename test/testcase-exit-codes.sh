@@ -0,0 +1,48 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -ux
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+expect_exit() {
+  local expected="$1"
+  shift
+  "$@"
+  local actual="$?"
+
+  if [[ "${actual}" -ne "${expected}" ]]; then
+    echo "expected exit code ${expected}, got ${actual}: $@"
+    exit 1
+  fi
+}
+
+echo "A" > testA
+echo "B" > testB
+touch -m --date='2016-01-01 06:00:01' testA testB
+
+expect_exit 4 ic status
+expect_exit 64 ic init --progress=off --checksum=crc32
+expect_exit 0 ic init --progress=off
+expect_exit 0 ic status
+expect_exit 64 ic status --invalid-flag
+expect_exit 64 ic invalid-command
+expect_exit 64 ic duplicates --min_size=99999999999T
+expect_exit 4 ic status -x .invalid
+
+echo "C" > testC
+expect_exit 1 ic status
+
+rm testC
+echo "X" > testB
+touch -m --date='2016-01-01 06:00:01' testB
+expect_exit 2 ic verify --progress=off
+
+echo "index corrupted" > $(ls .ic/*.idx)
+expect_exit 4 ic status
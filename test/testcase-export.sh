@@ -0,0 +1,51 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+mkdir dir
+echo "A" > testA
+echo "B" > "dir/test B"
+echo "C" > 'back\slash'
+echo "D" > $'new\nline'
+
+ic init --progress=off --set_time 1451624401000000
+
+ic export > ../export.sha256
+sha256sum -c ../export.sha256
+
+grep -qx "06f961b802bc46ee168555f066d28f4f0e9afdf3f88174c1ee6f9de004fc30a0  testA" ../export.sha256
+grep -qx "c0cde77fa8fef97d476c10aad3d2d54fcc2f336140d073651c2dcccf1e379fd6  dir/test B" ../export.sha256
+grep -q '^\\[0-9a-f]*  back\\\\slash$' ../export.sha256
+grep -q '^\\[0-9a-f]*  new\\nline$' ../export.sha256
+
+ic export --format=bsd-tag > ../export.tag
+sha256sum -c ../export.tag
+grep -qx "SHA256 (testA) = 06f961b802bc46ee168555f066d28f4f0e9afdf3f88174c1ee6f9de004fc30a0" ../export.tag
+
+# a plain format key only sets the output format of the text/json commands
+echo "format = json" >> .ic/config
+IC_FORMAT=jsonl ic export > ../export.sha256
+sha256sum -c ../export.sha256
+
+echo "export.format = bsd-tag" >> .ic/config
+ic export > ../export.tag
+grep -qx "SHA256 (testA) = 06f961b802bc46ee168555f066d28f4f0e9afdf3f88174c1ee6f9de004fc30a0" ../export.tag
+
+if ic export --format=md5sum; then
+  echo "md5sum export of a sha256 snapshot must fail"
+  exit 1
+fi
+
+rm -rf .ic
+ic init --progress=off --checksum=md5
+ic export --format=md5sum > ../export.md5
+md5sum -c ../export.md5
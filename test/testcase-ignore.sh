@@ -0,0 +1,66 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+mkdir -p docs/build node_modules/lib src/cache src/nested/cache
+echo "A" > docs/readme.txt
+echo "B" > docs/draft.tmp
+echo "C" > docs/keep.tmp
+echo "D" > docs/build/out.html
+echo "E" > node_modules/lib/index.js
+echo "F" > .DS_Store
+echo "G" > src/main.rs
+echo "H" > src/cache/data
+echo "I" > src/nested/cache/data
+echo "J" > cache
+
+(cat > ".icignore") <<EOF
+# temporary files
+*.tmp
+!keep.tmp
+.DS_Store
+node_modules/
+/docs/build
+EOF
+
+(cat > "src/.icignore") <<EOF
+cache/
+EOF
+
+ic init --progress=off --set_time 1451624401000000
+ic ls-files | awk '{ print $4 }' > ../files
+
+(cat > "../files.expected") <<EOF
+.icignore
+cache
+docs/keep.tmp
+docs/readme.txt
+src/.icignore
+src/main.rs
+EOF
+
+diff ../files ../files.expected
+
+# ignored files do not show up as changes
+echo "X" > docs/other.tmp
+echo "Y" > src/cache/more
+ic status
+
+# command line patterns take precedence over the .icignore files
+if ic status --include=src/cache/ --exclude=cache > ../status; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+grep -q 'deleted  "cache"' ../status
+grep -q 'created  "src/cache/data"' ../status
+grep -q 'created  "src/cache/more"' ../status
@@ -0,0 +1,90 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+mkdir dir
+echo "A" > testA
+echo "B" > "dir/test B"
+echo "C" > testC
+
+sha256sum testA "./dir/test B" > ../SHA256SUMS
+echo "0000000000000000000000000000000000000000000000000000000000000000  missing" >> ../SHA256SUMS
+
+# IC_FORMAT only sets the output format of the text/json commands
+if IC_FORMAT=json ic import --progress=off ../SHA256SUMS > ../import.out; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+grep -q "Imported: 2 files" ../import.out
+grep -q 'missing  "missing"' ../import.out
+grep -q 'unlisted "testC"' ../import.out
+
+# the unlisted file shows up as created
+if ic status > ../status.out; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+grep -q 'created  "testC"' ../status.out
+ic ack --noconfirm .
+ic verify --progress=off
+
+# imported checksums are validated by verify
+rm -rf .ic
+(cat > "../audit.txt") <<EOF
+%%%% HASHDEEP-1.0
+%%%% size,md5,filename
+## Invoked from: ${TEST_TMPDIR}/repo
+2,bf072e9119077b4e76437a93986787ef,${TEST_TMPDIR}/repo/testA
+2,00000000000000000000000000000000,${TEST_TMPDIR}/repo/testC
+2,$(md5sum < "dir/test B" | cut -d" " -f1),dir/test B
+EOF
+
+ic import --progress=off --format=hashdeep ../audit.txt
+
+set +e
+ic verify --progress=off > ../verify.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 2
+grep -q 'modified "testC"' ../verify.out
+if grep -q 'test B' ../verify.out; then
+  echo "dir/test B must be unchanged"
+  exit 1
+fi
+
+rm -rf .ic
+(cat > "../files.sfv") <<EOF
+; generated by hand
+testA 486E85A5
+dir/test B 6343D666
+testC 00000000
+EOF
+
+ic import --progress=off --format=sfv - < ../files.sfv 2> ../import.err
+grep -q "WARNING: CRC-32 is not an integrity hash" ../import.err
+
+ic ls-files > ../files.out
+grep -q "^486e85a5 " ../files.out
+
+if ic verify --progress=off > ../verify.out; then
+  echo "exit code must not be zero"
+  exit 1
+fi
+
+grep -q 'modified "testC"' ../verify.out
+if grep -q 'testA' ../verify.out; then
+  echo "testA must be unchanged"
+  exit 1
+fi
@@ -0,0 +1,46 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+cd "${TEST_TMPDIR}"
+
+mkdir -p a b/sub c
+echo "A" > a/testA
+echo "B" > b/sub/testB
+echo "C" > c/testC
+
+touch -m --date='2016-01-01 06:00:01' a/testA b/sub/testB c/testC
+
+ic index a/ ./b > index.actual
+
+(cat > "index.expected") <<EOF
+a/testA [sha256] 06f961b802bc46ee168555f066d28f4f0e9afdf3f88174c1ee6f9de004fc30a0 2 1451624401000000
+b/sub/testB [sha256] c0cde77fa8fef97d476c10aad3d2d54fcc2f336140d073651c2dcccf1e379fd6 2 1451624401000000
+EOF
+
+diff "index.actual" "index.expected"
+
+printf "c/testC\na/testA\n" > list
+ic index --files-from=list b > index.actual
+
+(cat > "index.expected") <<EOF
+a/testA [sha256] 06f961b802bc46ee168555f066d28f4f0e9afdf3f88174c1ee6f9de004fc30a0 2 1451624401000000
+b/sub/testB [sha256] c0cde77fa8fef97d476c10aad3d2d54fcc2f336140d073651c2dcccf1e379fd6 2 1451624401000000
+c/testC [sha256] 12f37a8a84034d3e623d726fe10e5031f4df997ac13f4d5571b5a90c41fb84fe 2 1451624401000000
+EOF
+
+diff "index.actual" "index.expected"
+
+echo "${TEST_TMPDIR}/c/testC" | ic index --files-from=- > index.actual
+grep -qx "${TEST_TMPDIR}/c/testC \[sha256\] 12f37a8a84034d3e623d726fe10e5031f4df997ac13f4d5571b5a90c41fb84fe 2 1451624401000000" index.actual
+
+if echo "missing" | ic index --files-from=-; then
+  echo "missing files must fail"
+  exit 1
+fi
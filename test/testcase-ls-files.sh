@@ -0,0 +1,72 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir -p "${TEST_TMPDIR}/repo/sub"
+cd "${TEST_TMPDIR}/repo"
+
+echo "A" > testA
+echo "B" > sub/testB
+
+touch -m --date='2016-01-01 06:00:01' testA
+touch -m --date='2016-01-01 06:00:02' sub/testB
+
+ic init --progress=off --set_time 1451624401000000
+
+echo "C" > sub/testC
+touch -m --date='2016-01-01 06:00:03' sub/testC
+ic ack -y -m "Hello World" --set_time 1451624402000000
+
+ic ls-files sub > "../output"
+
+(cat > "../output.expected") <<EOF
+c0cde77fa8fef97d476c10aad3d2d54fcc2f336140d073651c2dcccf1e379fd6            2 2016-01-01T06:00:02+01:00 sub/testB
+12f37a8a84034d3e623d726fe10e5031f4df997ac13f4d5571b5a90c41fb84fe            2 2016-01-01T06:00:03+01:00 sub/testC
+EOF
+
+diff "../output" "../output.expected"
+
+snapshot_first="$(ls .ic | sort | head -n 1 | cut -d- -f2)"
+ic ls-files --snapshot="${snapshot_first:0:8}" > "../output"
+
+(cat > "../output.expected") <<EOF
+c0cde77fa8fef97d476c10aad3d2d54fcc2f336140d073651c2dcccf1e379fd6            2 2016-01-01T06:00:02+01:00 sub/testB
+06f961b802bc46ee168555f066d28f4f0e9afdf3f88174c1ee6f9de004fc30a0            2 2016-01-01T06:00:01+01:00 testA
+EOF
+
+diff "../output" "../output.expected"
+
+ic show --colours=off "${snapshot_first:0:8}" > "../output"
+
+(cat > "../output.expected") <<EOF
+snapshot ${snapshot_first%.idx}
+Timestamp: Fri, 01 Jan 2016 06:00:01 +0100 (1451624401000000)
+Checksum Function: sha256
+Size: 4B (2 files)
+
+    <no message>
+
+EOF
+
+diff "../output" "../output.expected"
+
+# special characters are escaped, so that every file is printed on one line
+printf 'D' > "$(printf 'sub/new\nline')"
+printf 'E' > "sub/with space"
+ic ack -y --set_time 1451624403000000
+ic ls-files sub | awk '{ print $4 }' > "../output"
+
+(cat > "../output.expected") <<'EOF'
+sub/new\nline
+sub/testB
+sub/testC
+sub/with\_space
+EOF
+
+diff "../output" "../output.expected"
@@ -0,0 +1,57 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+mkdir dir
+echo "A" > testA
+echo "B" > "dir/test B"
+chmod 0640 testA
+touch -m --date='2016-01-01 06:00:01' testA "dir/test B"
+
+ic init --progress=off --set_time 1451624401000000
+ic export --format=mtree > ../spec.mtree
+
+owner="uid=$(id -u) gid=$(id -g)"
+grep -qx "#mtree" ../spec.mtree
+grep -qx "./dir type=dir mode=0755 ${owner}" ../spec.mtree
+grep -qx "./testA type=file mode=0640 ${owner} size=2 time=1451624401.000000000 sha256digest=06f961b802bc46ee168555f066d28f4f0e9afdf3f88174c1ee6f9de004fc30a0" ../spec.mtree
+grep -q "^./dir/test\\\\040B type=file " ../spec.mtree
+
+rm -rf .ic
+ic import --progress=off --format=mtree ../spec.mtree
+ic verify --progress=off
+
+# a spec in the classic format with relative names
+rm -rf .ic
+(cat > "../classic.mtree") <<EOF
+#	   user: root
+/set type=file uid=0 gid=0 mode=0644
+.               type=dir mode=0755
+    testA       size=2 \\
+                sha256digest=06f961b802bc46ee168555f066d28f4f0e9afdf3f88174c1ee6f9de004fc30a0
+dir             type=dir
+    test\\040B  size=2 sha256digest=0000000000000000000000000000000000000000000000000000000000000000
+    link        type=link link=../testA
+..
+EOF
+
+ic import --progress=off --format=mtree ../classic.mtree
+ic ls-files > ../files.out
+test "$(wc -l < ../files.out)" = 2
+
+set +e
+ic verify --progress=off > ../verify.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 2
+grep -q 'modified "dir/test B"' ../verify.out
@@ -0,0 +1,120 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+(cat > "../sendmail") <<EOF
+#!/bin/bash
+echo "\$@" > "${TEST_TMPDIR}/mail.args"
+cat > "${TEST_TMPDIR}/mail"
+EOF
+
+chmod +x "../sendmail"
+
+echo "A" > testA
+echo "B" > testB
+touch -m --date='2016-01-01 06:00:01' testA testB
+
+ic init --progress=off --set_time 1451624401000000
+
+(cat > ".ic/config") <<EOF
+# notify about deletions only
+notify_on = deleted
+notify_sendmail = admin@example.com
+notify_sendmail_command = ${TEST_TMPDIR}/sendmail
+notify_command = cat > ${TEST_TMPDIR}/report.json
+EOF
+
+ic status
+
+echo "C" > testC
+if ic status; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+test ! -e "../mail"
+test ! -e "../report.json"
+
+rm testA
+if ic status; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+(cat > "../mail.expected") <<EOF
+To: admin@example.com
+Subject: [integritycheck] $(pwd -P): 2 changes
+
+Repository: $(pwd -P)
+Last Snapshot: Fri, 01 Jan 2016 06:00:01 +0100
+Status: DIRTY
+
+    deleted  "testA"
+    created  "testC"
+EOF
+
+diff "../mail" "../mail.expected"
+grep -qx -- "-t -oi" "../mail.args"
+
+grep -q '"command":"status"' "../report.json"
+grep -q '"diffs":\[{"kind":"deleted","path":"testA"},{"kind":"created","path":"testC"}\]' "../report.json"
+
+echo "notify_webhook = http://127.0.0.1:1/" >> ".ic/config"
+if ic status 2> "../stderr"; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+grep -q "WARNING: failed to call webhook http://127.0.0.1:1/" "../stderr"
+
+# the report is posted to a local HTTP listener
+python3 - "../webhook.port" "../webhook.body" <<'EOF' &
+import http.server, os, sys
+
+class Handler(http.server.BaseHTTPRequestHandler):
+  def do_POST(self):
+    body = self.rfile.read(int(self.headers["Content-Length"]))
+    open(sys.argv[2], "wb").write(body)
+    self.send_response(200)
+    self.end_headers()
+
+  def log_message(self, *args):
+    pass
+
+server = http.server.HTTPServer(("127.0.0.1", 0), Handler)
+open(sys.argv[1] + ".tmp", "w").write(str(server.server_port))
+os.rename(sys.argv[1] + ".tmp", sys.argv[1])
+server.handle_request()
+EOF
+pid=$!
+
+for i in $(seq 1 100); do
+  if [[ -s "../webhook.port" ]]; then
+    break
+  fi
+
+  sleep 0.05
+done
+
+sed -i "s|^notify_webhook = .*|notify_webhook = http://127.0.0.1:$(cat ../webhook.port)/hook|" ".ic/config"
+if ic status 2> "../stderr"; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+wait "${pid}"
+grep -q '"command":"status"' "../webhook.body"
+grep -q '"diffs":\[{"kind":"deleted","path":"testA"},{"kind":"created","path":"testC"}\]' "../webhook.body"
+if grep -q "WARNING" "../stderr"; then
+  echo "the webhook must succeed"
+  exit 1
+fi
@@ -0,0 +1,37 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+echo "A" > testA
+echo "B" > testB
+
+ic init --progress=off --set_time 1451624401000000
+ic verify --progress=off --prometheus_file=../metrics.prom
+
+labels="command=\"verify\",repository=\"$(pwd -P)\""
+grep -qx "integritycheck_changes{${labels},kind=\"created\"} 0" ../metrics.prom
+grep -qx "integritycheck_snapshot_files{${labels}} 2" ../metrics.prom
+grep -qx "integritycheck_snapshot_bytes{${labels}} 4" ../metrics.prom
+grep -qx "integritycheck_snapshot_timestamp_seconds{${labels}} 1451624401" ../metrics.prom
+grep -qx "integritycheck_scan_bytes{${labels}} 4" ../metrics.prom
+last_success="$(grep "^integritycheck_last_success_timestamp_seconds{${labels}} " ../metrics.prom)"
+
+echo "C" > testC
+
+if ic verify --progress=off --prometheus_file=../metrics.prom; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+grep -qx "integritycheck_changes{${labels},kind=\"created\"} 1" ../metrics.prom
+grep -qx "${last_success}" ../metrics.prom
+test ! -e ../metrics.prom.tmp
@@ -0,0 +1,90 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+head -c 100000 /dev/urandom > testA
+echo "B" > testB
+touch testC
+cp -p testA ../testA.orig
+ic init --progress=off
+
+ic protect --progress=off --block_size=1K > ../protect.out
+grep -qx "Files protected: 2" ../protect.out
+test -d .ic/parity
+ic status
+
+# already protected files are skipped
+ic protect --progress=off > ../protect.out
+grep -qx "Files already protected: 2" ../protect.out
+
+# intact files need no repair
+ic repair --output=../testA.repaired testA
+test ! -e ../testA.repaired
+
+# damage 10 of the 98 blocks (and leave the modification time untouched)
+for offset in 0 5000 20000 33000 41000 52000 60000 71000 88000 99990; do
+  printf 'XXXXXXXXXX' | dd of=testA bs=1 seek=${offset} conv=notrunc status=none
+done
+
+touch -r ../testA.orig testA
+
+set +e
+ic verify --progress=off > /dev/null
+exit_code=$?
+set -e
+
+test "${exit_code}" = 2
+
+ic repair --colours=off --output=../testA.repaired testA > ../repair.out
+grep -qx "Damaged blocks: 10 of 98" ../repair.out
+grep -qx "Intact recovery blocks: 10 of 10" ../repair.out
+cmp ../testA.repaired ../testA.orig
+
+# the output file is never overwritten
+if ic repair --output=../testA.repaired testA; then
+  echo "existing output files must fail"
+  exit 1
+fi
+
+# too many damaged blocks
+printf 'XXXXXXXXXX' | dd of=testA bs=1 seek=10000 conv=notrunc status=none
+
+set +e
+ic repair --colours=off --output=../testA.failed testA > ../repair.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 2
+grep -qx "Damaged blocks: 11 of 98" ../repair.out
+test ! -e ../testA.failed
+
+# a missing file can be reconstructed if it consists of a single block
+rm testB
+ic repair --output=../testB.repaired testB
+test "$(cat ../testB.repaired)" = "B"
+
+# a truncated file
+cp ../testA.orig testA
+truncate -s 95000 testA
+ic repair --output=../testA.truncated testA
+cmp ../testA.truncated ../testA.orig
+
+# files that do not match the snapshot are not protected
+rm -rf .ic/parity
+set +e
+ic protect --progress=off --colours=off > ../protect.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 1
+grep -q 'mismatch "testA"' ../protect.out
+grep -q 'mismatch "testB"' ../protect.out
@@ -0,0 +1,72 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+mkdir dir
+echo "A" > testA
+echo "B" > dir/testB
+echo "C" > testC
+echo "D" > testD
+touch -m --date='2016-01-01 06:00:01' testA dir/testB testC testD
+ic init --progress=off
+
+# the backup is reorganized and one file is missing
+mkdir -p ../backup/old
+cp testA ../backup/testA
+cp dir/testB ../backup/old/testB-copy
+echo "X" > ../backup/testC
+
+# damage the repository
+echo "Z" > testA
+touch -m --date='2016-01-01 06:00:01' testA
+rm dir/testB
+rm testC
+find . -type f | sort > ../repo.before
+
+set +e
+ic recover --progress=off --colours=off --from=../backup --to=../staging > ../recover.out
+exit_code=$?
+set -e
+
+test "${exit_code}" = 1
+
+(cat > "../recover.expected") <<EOF
+Staging Directory: ${TEST_TMPDIR}/staging
+Files recovered: 2
+Files not found: 1
+
+    recovered "dir/testB" (from "old/testB-copy")
+    recovered "testA"
+    not found "testC"
+
+EOF
+
+diff ../recover.out ../recover.expected
+test "$(cat ../staging/testA)" = "A"
+test "$(cat ../staging/dir/testB)" = "B"
+test ! -e ../staging/testC
+
+# the repository is left untouched
+find . -type f | sort > ../repo.after
+diff ../repo.before ../repo.after
+
+# explicit paths are recovered even if they were not damaged
+cp testD ../backup/testD
+ic recover --progress=off --from=../backup --to=../staging2 testD > ../recover.out
+grep -qx "Files recovered: 1" ../recover.out
+test "$(cat ../staging2/testD)" = "D"
+
+if ic recover --progress=off --from=../backup --to=./staging; then
+  echo "staging directories inside the repository must fail"
+  exit 1
+fi
+test ! -e staging
@@ -0,0 +1,71 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+echo "A" > fileA
+echo "B" > fileB
+echo "C" > fileC
+
+ic init --progress=off --set_time 1451624401000000
+
+# interrupt a verify run while it reads a large new file. The file is checked
+# after all others and takes far longer to read than it takes to notice the
+# first checkpoint; it is only created now so that init does not read it
+truncate -s 128M zz-large
+ic verify --progress=off --checkpoint_interval=0s &
+pid=$!
+
+for i in $(seq 1 1000); do
+  if [[ -e .ic/verify/checkpoint ]]; then
+    break
+  fi
+
+  sleep 0.01
+done
+
+# fails if the run was not interrupted
+kill -TERM "${pid}"
+wait "${pid}" || true
+rm zz-large
+test -e .ic/verify/checkpoint
+
+# files from the checkpoint are not read again unless their metadata changed
+cp -p fileA ../fileA.orig
+echo "X" > fileA
+touch -r ../fileA.orig fileA
+touch -m --date='2016-01-02 06:00:01' fileB
+
+if ic verify --progress=off --colours=off --resume > ../verify.out; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+grep -qx '    modified "fileB" (metadata modifications only)' ../verify.out
+grep -c '^    ' ../verify.out > ../verify.count || true
+test "$(cat ../verify.count)" = 1
+test ! -e .ic/verify/checkpoint
+
+if ic verify --progress=off; then
+  echo "exit code must be two"
+  exit 1
+fi
+
+# without a checkpoint, all files are verified
+cp -p ../fileA.orig fileA
+touch -r ../fileA.orig fileB
+ic verify --progress=off --resume 2> ../verify.err
+grep -q "no checkpoint found" ../verify.err
+
+if ic verify --progress=off --resume --fraction=5%; then
+  echo "--resume and --fraction must not be combined"
+  exit 1
+fi
@@ -0,0 +1,74 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+for i in 0 1 2 3 4 5 6 7 8 9; do
+  echo "A${i}" > "file${i}"
+done
+
+ic init --progress=off --set_time 1451624401000000
+
+ic verify --progress=off --colours=off --fraction=20% > ../verify.out
+grep -qx "Files Verified: 2 of 10" ../verify.out
+test "$(wc -l < .ic/verify/state)" = 2
+
+# the least recently verified files are checked first
+for i in 1 2 3 4; do
+  ic verify --progress=off --fraction=20%
+done
+
+test "$(wc -l < .ic/verify/state)" = 10
+test "$(cut -d " " -f 3 .ic/verify/state | sort | uniq | wc -l)" = 10
+
+# corrupted files stay at the front of the queue
+cp -p file0 ../file0.orig
+echo "B0" > file0
+touch -r ../file0.orig file0
+
+for i in 1 2; do
+  if ic verify --progress=off --fraction=10%; then
+    echo "exit code must be two"
+    exit 1
+  fi
+done
+
+cp -p ../file0.orig file0
+ic verify --progress=off --fraction=10%
+
+# changes are detected even if no file is verified
+echo "C" > fileC
+ic verify --progress=off --colours=off --budget=0s > ../verify.out || true
+grep -qx "Files Verified: 0 of 10" ../verify.out
+grep -q "created  \"fileC\"" ../verify.out
+rm fileC
+
+ic verify --progress=off --budget=1h --prometheus_file=../metrics.prom
+labels="command=\"verify\",repository=\"$(pwd -P)\""
+grep -q "^integritycheck_oldest_verification_age_seconds{${labels}} " ../metrics.prom
+
+ic verify --progress=off --fraction=50% --format=json > ../verify.json
+grep -q '"files_verified":5' ../verify.json
+
+# an index directory that can not be written to only causes warnings
+rm -rf .ic/verify
+mkdir -p .ic/verify/state.tmp .ic/verify/checkpoint.tmp
+ic verify --progress=off --checkpoint_interval=0s 2> ../verify.err
+grep -q "WARNING: error while writing verify state file" ../verify.err
+grep -q "WARNING: failed to save checkpoint" ../verify.err
+ic verify --progress=off --fraction=50% 2> ../verify.err
+grep -q "WARNING: error while writing verify state file" ../verify.err
+rm -rf .ic/verify
+
+if ic verify --progress=off --fraction=0%; then
+  echo "an empty fraction must be rejected"
+  exit 1
+fi
@@ -0,0 +1,48 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+echo "A" > testA
+echo "B" > testB
+echo "C" > testC
+
+touch -m --date='2016-01-01 06:00:01' testA
+touch -m --date='2016-01-01 06:00:02' testB
+touch -m --date='2016-01-01 06:00:03' testC
+
+ic init --progress=off --set_time 1451624401000000
+
+mv testC "test C"
+rm testA
+echo "D" > testD
+
+if ic status --format=jsonl > "../status.raw"; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+snapshot="$(ls .ic | cut -d- -f2 | cut -d. -f1)"
+repository="$(pwd -P)"
+
+(cat > "../status.expected") <<EOF
+{"type":"summary","command":"status","repository":"${repository}","snapshot":{"checksum":"${snapshot}","timestamp_us":1451624401000000},"total_files":3,"total_bytes":6,"status":"dirty","counts":{"created":1,"deleted":1,"modified":0,"metadata_modified":0,"renamed":1}}
+{"type":"diff","kind":"deleted","path":"testA"}
+{"type":"diff","kind":"renamed","from":"testC","to":"test C"}
+{"type":"diff","kind":"created","path":"testD"}
+EOF
+
+diff "../status.raw" "../status.expected"
+
+ic ack -y --format=json --set_time 1451624402000000 > /dev/null
+ic status --format=json > "../status.raw"
+grep -q '"status":"clean"' "../status.raw"
+grep -q '"diffs":\[\]' "../status.raw"
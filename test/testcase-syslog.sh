@@ -0,0 +1,73 @@
+#!/bin/bash
+# integritycheck - https://github.com/asmuth/integritycheck
+# Copyright (c) 2018, Paul Asmuth <paul@asmuth.com>
+#
+# This file is part of the "integritycheck" project. integritycheck is free software
+# licensed under the Apache License, Version 2.0 (the "License"); you may not
+# use this file except in compliance with the License.
+set -uex
+
+source test/test-util.sh
+mkdir "${TEST_TMPDIR}/repo"
+cd "${TEST_TMPDIR}/repo"
+
+listen() {
+  python3 -c '
+import socket, sys
+sock = socket.socket(socket.AF_UNIX, socket.SOCK_DGRAM)
+sock.bind(sys.argv[1])
+sock.settimeout(10)
+with open(sys.argv[2], "wb") as f:
+  for i in range(int(sys.argv[3])):
+    f.write(sock.recv(65536) + b"\n--\n")
+' "$@"
+}
+
+echo "A" > testA
+echo "B" > testB
+ic init --progress=off --set_time 1451624401000000
+
+rm testA
+echo "C" > testC
+
+listen "../syslog.sock" "../syslog" 3 &
+listener=$!
+sleep 1
+
+if ic status --log=syslog:../syslog.sock --syslog_enterprise_number=32473; then
+  echo "exit code must be one"
+  exit 1
+fi
+
+wait $listener
+
+repository="$(pwd -P)"
+grep -q "^<11>1 .* integritycheck [0-9]* status \[origin software=\"integritycheck\" swVersion=\"[0-9.]*\"\]\[integritycheck@32473 command=\"status\" repository=\"${repository}\" snapshot=\"[0-9a-f]*\" status=\"dirty\" created=\"1\" deleted=\"1\" [^]]* known_bad=\"0\"\] status ${repository}: dirty, 1 created, 1 deleted$" ../syslog
+grep -q "^<11>1 .* \[origin [^]]*\]\[integritycheck@32473 command=\"status\" repository=\"${repository}\" kind=\"deleted\" path=\"testA\"\] ${repository}: deleted  \"testA\"$" ../syslog
+grep -q "^<13>1 .* \[origin [^]]*\]\[integritycheck@32473 [^]]* kind=\"created\" path=\"testC\"\] ${repository}: created  \"testC\"$" ../syslog
+
+# the structured data of syslog messages needs a private enterprise number
+set +e
+ic status --log=syslog:../syslog.sock
+exit_code=$?
+set -e
+
+test "${exit_code}" = 64
+
+listen "../journal.sock" "../journal" 3 &
+listener=$!
+sleep 1
+
+ic ack --noconfirm --log=journald:../journal.sock .
+
+wait $listener
+
+grep -qx "MESSAGE=ack $(pwd -P): dirty, 1 created, 1 deleted" ../journal
+grep -qx "PRIORITY=3" ../journal
+grep -qx "SYSLOG_IDENTIFIER=integritycheck" ../journal
+grep -qx "IC_COMMAND=ack" ../journal
+
+if ic status --log=foo; then
+  echo "invalid log target must fail"
+  exit 1
+fi